@@ -0,0 +1,176 @@
+use std::io;
+
+use crate::decoder::{write_string, Escape, FmtSink, IoSink, Sink};
+use crate::error::{Error, Result};
+use crate::slice::{JsoncSlice, ValueKind};
+
+/// Encodes `json` in the canonical form of RFC 8785, the JSON Canonicalization
+/// Scheme, so that equal documents encode to the same bytes:
+///
+/// - no whitespace,
+/// - object keys sorted by their UTF-16 code units,
+/// - numbers written as ECMAScript writes them, with the fewest digits that
+///   read back as the same `f64`, such as `1e+21`, `0.002` and `1e-27`,
+/// - strings escaped only where JSON requires it.
+///
+/// A key repeated in one object fails with [`Error::DuplicateKey`], and a NaN
+/// or infinite number with [`Error::NonFiniteNumber`].
+pub fn encode_canonical(json: JsoncSlice) -> Result<String> {
+    let mut result = String::new();
+    write_canonical(&mut FmtSink(&mut result), json)?;
+    Ok(result)
+}
+
+/// Like [`encode_canonical`], writing to `writer`.
+pub fn encode_canonical_to<W: io::Write + ?Sized>(json: JsoncSlice, writer: &mut W) -> Result<()> {
+    write_canonical(&mut IoSink(writer), json)
+}
+
+fn write_canonical<S: Sink + ?Sized>(sink: &mut S, json: JsoncSlice) -> Result<()> {
+    match json.kind() {
+        Some(ValueKind::Object) => {
+            let mut entries: Vec<_> = json.object_iter().unwrap().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(Error::DuplicateKey(pair[0].0.to_string()));
+            }
+            sink.write_str("{")?;
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    sink.write_str(",")?;
+                }
+                write_string(sink, key, Escape::default())?;
+                sink.write_str(":")?;
+                write_canonical(sink, value)?;
+            }
+            sink.write_str("}")
+        }
+        Some(ValueKind::Array) => {
+            sink.write_str("[")?;
+            for (i, element) in json.array_iter().unwrap().enumerate() {
+                if i > 0 {
+                    sink.write_str(",")?;
+                }
+                write_canonical(sink, element)?;
+            }
+            sink.write_str("]")
+        }
+        Some(ValueKind::String) => write_string(sink, json.as_str().unwrap(), Escape::default()),
+        Some(ValueKind::Number) => write_es_number(sink, json.as_f64().unwrap()),
+        Some(ValueKind::Bool) => sink.write_str(if json.as_bool().unwrap() {
+            "true"
+        } else {
+            "false"
+        }),
+        _ => sink.write_str("null"),
+    }
+}
+
+/// Writes `n` as ECMAScript's `Number.prototype.toString` does.
+fn write_es_number<S: Sink + ?Sized>(sink: &mut S, n: f64) -> Result<()> {
+    if !n.is_finite() {
+        return Err(Error::NonFiniteNumber(n));
+    }
+    if n == 0.0 {
+        return sink.write_str("0");
+    }
+    if n < 0.0 {
+        sink.write_str("-")?;
+    }
+    // `{:e}` gives the shortest digits that round-trip, as `d.ddde<exp>`.
+    let sci = format!("{:e}", n.abs());
+    let (mantissa, exp) = sci.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    // The value is `0.digits * 10^n`.
+    let n = exp.parse::<i32>().unwrap() + 1;
+    if k <= n && n <= 21 {
+        sink.write_str(&digits)?;
+        sink.write_str(&"0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        let (int, frac) = digits.split_at(n as usize);
+        sink.write_fmt(format_args!("{}.{}", int, frac))
+    } else if -6 < n && n <= 0 {
+        sink.write_fmt(format_args!("0.{}{}", "0".repeat(-n as usize), digits))
+    } else {
+        let (first, rest) = digits.split_at(1);
+        sink.write_str(first)?;
+        if !rest.is_empty() {
+            sink.write_fmt(format_args!(".{}", rest))?;
+        }
+        let sign = if n > 0 { "+" } else { "-" };
+        sink.write_fmt(format_args!("e{}{}", sign, (n - 1).abs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_value;
+    use crate::value::{Jsonc, Node};
+
+    fn canonical(json: &str) -> Result<String> {
+        encode_canonical(parse_value(json.as_bytes()).as_slice())
+    }
+
+    #[test]
+    fn test_encode_canonical() {
+        let json = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        assert_eq!(
+            canonical(json).unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+
+        let json = r#"{"\u20ac": 1, "\r": 2, "\ufb33": 3, "1": 4, "\ud83d\ude00": 5, "\u0080": 6, "\u00f6": 7}"#;
+        assert_eq!(
+            canonical(json).unwrap(),
+            "{\"\\r\":2,\"1\":4,\"\u{80}\":6,\"ö\":7,\"€\":1,\"😀\":5,\"\u{fb33}\":3}"
+        );
+
+        let mut bytes = Vec::new();
+        let json = parse_value(br#"{"b": [], "a": {"d": {}, "c": null}}"#);
+        encode_canonical_to(json.as_slice(), &mut bytes).unwrap();
+        assert_eq!(bytes, br#"{"a":{"c":null,"d":{}},"b":[]}"#);
+
+        assert!(matches!(
+            canonical(r#"{"a": 1, "b": {"c": 1, "c": 1}}"#),
+            Err(Error::DuplicateKey(key)) if key == "c"
+        ));
+    }
+
+    #[test]
+    fn test_es_numbers() {
+        let number = |n: f64| {
+            let json = Jsonc::new_with_values(vec![Node::Number], vec![], vec![n]);
+            encode_canonical(json.as_slice())
+        };
+        for (n, expected) in [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-1.5, "-1.5"),
+            (1e20, "100000000000000000000"),
+            (1e21, "1e+21"),
+            (123456789012345680000.0, "123456789012345680000"),
+            (1.5e300, "1.5e+300"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (-1.25e-7, "-1.25e-7"),
+            (5e-324, "5e-324"),
+            (f64::MAX, "1.7976931348623157e+308"),
+            (9007199254740993.0, "9007199254740992"),
+            (0.1 + 0.2, "0.30000000000000004"),
+        ] {
+            assert_eq!(number(n).unwrap(), expected, "{}", n);
+        }
+        assert!(matches!(number(f64::NAN), Err(Error::NonFiniteNumber(_))));
+        assert!(matches!(
+            number(f64::NEG_INFINITY),
+            Err(Error::NonFiniteNumber(_))
+        ));
+    }
+}
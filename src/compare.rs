@@ -0,0 +1,256 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use crate::slice::{JsoncSlice, ValueKind};
+use crate::value::Jsonc;
+
+impl<'a> JsoncSlice<'a> {
+    /// Compares two values by meaning rather than by layout: object entries
+    /// may appear in any order and numbers are compared by value, so `0` and
+    /// `-0` are equal. This is the equality behind `==` on `Jsonc` and
+    /// `JsoncSlice`.
+    pub fn semantic_eq(&self, other: &JsoncSlice) -> bool {
+        self.semantic_cmp(other) == Ordering::Equal
+    }
+
+    /// Compares two values using the ordering of PostgreSQL `jsonb`:
+    ///
+    /// - Object > Array > Boolean > Number > String > Null.
+    /// - An object with more entries is greater than one with fewer entries.
+    /// - Objects with the same number of entries compare key 1, value 1,
+    ///   key 2, and so on, with the keys of each object taken shortest first
+    ///   and then by bytes.
+    /// - An array with more elements is greater than one with fewer elements.
+    ///   Arrays of the same length compare element by element.
+    /// - Strings compare by bytes, `false < true`, and numbers compare by
+    ///   value with NaN above every other number.
+    ///
+    /// An empty slice, which holds no value, is less than any value. When an
+    /// object repeats a key only its last entry is considered, since `jsonb`
+    /// keeps the last value.
+    pub fn semantic_cmp(&self, other: &JsoncSlice) -> Ordering {
+        let (kind, other_kind) = (self.kind(), other.kind());
+        let ordering = rank(kind).cmp(&rank(other_kind));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        match kind {
+            None | Some(ValueKind::Null) => Ordering::Equal,
+            Some(ValueKind::Bool) => self.as_bool().cmp(&other.as_bool()),
+            Some(ValueKind::Number) => {
+                let (a, b) = (self.as_f64().unwrap(), other.as_f64().unwrap());
+                normalize(a).total_cmp(&normalize(b))
+            }
+            Some(ValueKind::String) => self.as_str().cmp(&other.as_str()),
+            Some(ValueKind::Array) => {
+                let ordering = self.len().cmp(&other.len());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                for (a, b) in self.array_iter().unwrap().zip(other.array_iter().unwrap()) {
+                    let ordering = a.semantic_cmp(&b);
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                }
+                Ordering::Equal
+            }
+            Some(ValueKind::Object) => {
+                let (a, b) = (sorted_entries(self), sorted_entries(other));
+                let ordering = a.len().cmp(&b.len());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                for ((a_key, a_value), (b_key, b_value)) in a.iter().zip(b.iter()) {
+                    let ordering =
+                        cmp_keys(a_key, b_key).then_with(|| a_value.semantic_cmp(b_value));
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                }
+                Ordering::Equal
+            }
+        }
+    }
+
+    /// Feeds the value into `state` consistently with [`JsoncSlice::semantic_eq`].
+    pub fn semantic_hash<H: Hasher>(&self, state: &mut H) {
+        let kind = self.kind();
+        rank(kind).hash(state);
+        match kind {
+            None | Some(ValueKind::Null) => {}
+            Some(ValueKind::Bool) => self.as_bool().hash(state),
+            Some(ValueKind::Number) => normalize(self.as_f64().unwrap()).to_bits().hash(state),
+            Some(ValueKind::String) => self.as_str().hash(state),
+            Some(ValueKind::Array) => {
+                self.len().hash(state);
+                for element in self.array_iter().unwrap() {
+                    element.semantic_hash(state);
+                }
+            }
+            Some(ValueKind::Object) => {
+                let entries = sorted_entries(self);
+                entries.len().hash(state);
+                for (key, value) in entries {
+                    key.hash(state);
+                    value.semantic_hash(state);
+                }
+            }
+        }
+    }
+}
+
+fn rank(kind: Option<ValueKind>) -> u8 {
+    match kind {
+        None => 0,
+        Some(ValueKind::Null) => 1,
+        Some(ValueKind::String) => 2,
+        Some(ValueKind::Number) => 3,
+        Some(ValueKind::Bool) => 4,
+        Some(ValueKind::Array) => 5,
+        Some(ValueKind::Object) => 6,
+    }
+}
+
+/// Maps `-0.0` to `0.0` and every NaN to the same NaN, so that `total_cmp`
+/// and the bit pattern only tell apart numbers with different values.
+fn normalize(n: f64) -> f64 {
+    if n == 0.0 {
+        0.0
+    } else if n.is_nan() {
+        f64::NAN
+    } else {
+        n
+    }
+}
+
+fn cmp_keys(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// The entries of an object in key order, keeping only the last entry of a
+/// repeated key.
+fn sorted_entries<'a>(object: &JsoncSlice<'a>) -> Vec<(&'a str, JsoncSlice<'a>)> {
+    // Reversed so that the stable sort puts the last entry of a repeated key
+    // first, where `dedup_by` keeps it.
+    let mut entries: Vec<_> = object.object_iter().unwrap().rev().collect();
+    entries.sort_by(|(a, _), (b, _)| cmp_keys(a, b));
+    entries.dedup_by(|(a, _), (b, _)| a == b);
+    entries
+}
+
+impl PartialEq for JsoncSlice<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.semantic_eq(other)
+    }
+}
+
+impl Eq for JsoncSlice<'_> {}
+
+impl PartialOrd for JsoncSlice<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for JsoncSlice<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.semantic_cmp(other)
+    }
+}
+
+impl Hash for JsoncSlice<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.semantic_hash(state);
+    }
+}
+
+impl PartialEq for Jsonc {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice().semantic_eq(&other.as_slice())
+    }
+}
+
+impl Eq for Jsonc {}
+
+impl PartialOrd for Jsonc {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Jsonc {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().semantic_cmp(&other.as_slice())
+    }
+}
+
+impl Hash for Jsonc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().semantic_hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::parser::parse_value;
+
+    #[test]
+    fn test_semantic_eq() {
+        let a = parse_value(br#"{"a":1,"b":[0,{"c":null}]}"#);
+        let b = parse_value(br#"{"b":[-0.0,{"c":null}],"a":1.0}"#);
+        let c = parse_value(br#"{"b":[0,{"c":null}],"a":2}"#);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(parse_value(b"[1,2]"), parse_value(b"[2,1]"));
+        assert_eq!(parse_value(br#"{"a":1,"a":2}"#), parse_value(br#"{"a":2}"#));
+        assert_ne!(parse_value(br#"{"a":1,"a":2}"#), parse_value(br#"{"a":1}"#));
+
+        let set: HashSet<Jsonc> = [a, b, c].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_semantic_cmp() {
+        let mut values: Vec<Jsonc> = [
+            r#"{"b":1}"#,
+            r#"{"aa":1}"#,
+            r#"{"a":1,"b":2}"#,
+            r#"[1,2]"#,
+            r#"[3]"#,
+            "true",
+            "false",
+            "10",
+            "-1",
+            r#""b""#,
+            r#""a""#,
+            "null",
+        ]
+        .iter()
+        .map(|s| parse_value(s.as_bytes()))
+        .collect();
+        values.reverse();
+        values.sort();
+        let sorted: Vec<String> = values.iter().map(crate::decoder::decode).collect();
+        assert_eq!(
+            sorted,
+            [
+                "null",
+                r#""a""#,
+                r#""b""#,
+                "-1",
+                "10",
+                "false",
+                "true",
+                "[3]",
+                "[1,2]",
+                r#"{"b":1}"#,
+                r#"{"aa":1}"#,
+                r#"{"a":1,"b":2}"#,
+            ]
+        );
+    }
+}
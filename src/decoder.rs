@@ -1,64 +1,749 @@
-use crate::{slice::JsoncSlice, value::{Jsonc, Node}};
-use jsonb::functions::escape_scalar_string;
+use std::cmp::Ordering;
+use std::fmt::{self, Write as _};
+use std::io;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::path::PathItem;
+use crate::slice::{JsoncSlice, ValueKind};
+use crate::value::{Jsonc, Node};
+use crate::visitor::{walk, Visitor};
 
 pub fn decode(json: &Jsonc) -> String {
     decode_slice(json.into())
 }
 
+/// Encodes `json` as compact JSON. A NaN or infinite number, which only a
+/// hand-built `Jsonc` can hold, is written as `null`; see [`decode_with`] for
+/// other choices.
 pub fn decode_slice(json: JsoncSlice) -> String {
+    decode_with(json, &LOSSY).expect("writing to a String cannot fail")
+}
+
+/// What to write for a NaN or infinite number, which JSON cannot represent.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum NonFinite {
+    /// Fail with [`Error::NonFiniteNumber`].
+    #[default]
+    Error,
+    /// Write `null`, as JavaScript's `JSON.stringify` does.
+    Null,
+    /// Write `NaN`, `Infinity` or `-Infinity`, as JSON5 allows.
+    Literal,
+}
+
+/// Compares two keys for [`KeyOrder::By`].
+pub type KeyComparator = dyn Fn(&str, &str) -> Ordering + Send + Sync;
+
+/// The order in which object entries are written.
+#[derive(Clone, Default)]
+pub enum KeyOrder {
+    /// The order of the document.
+    #[default]
+    Document,
+    /// Sorted by the bytes of the keys, as [`str::cmp`] orders them.
+    Bytes,
+    /// Sorted by a comparator, which may hold state such as a collator.
+    By(Arc<KeyComparator>),
+}
+
+impl fmt::Debug for KeyOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyOrder::Document => f.write_str("Document"),
+            KeyOrder::Bytes => f.write_str("Bytes"),
+            KeyOrder::By(_) => f.write_str("By(..)"),
+        }
+    }
+}
+
+impl KeyOrder {
+    /// The entries of the object `json` in this order. The sort is stable,
+    /// so entries with the same key keep their order.
+    fn entries<'a>(&self, json: JsoncSlice<'a>) -> Vec<(&'a str, JsoncSlice<'a>)> {
+        let mut entries: Vec<_> = json.object_iter().unwrap().collect();
+        match self {
+            KeyOrder::Document => {}
+            KeyOrder::Bytes => entries.sort_by(|(a, _), (b, _)| a.cmp(b)),
+            KeyOrder::By(compare) => entries.sort_by(|(a, _), (b, _)| compare(a, b)),
+        }
+        entries
+    }
+}
+
+/// Characters to escape in strings and keys beyond those JSON requires.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Escape {
+    /// Escape every non-ASCII character as `\uXXXX`, with a surrogate pair
+    /// for those beyond the Basic Multilingual Plane, for transports that
+    /// are not 8-bit clean.
+    pub ascii_only: bool,
+    /// Escape `<`, `>`, `&`, U+2028 and U+2029, so the output can be placed
+    /// in an HTML `<script>` element or in JavaScript source.
+    pub html_safe: bool,
+}
+
+/// How [`decode_with`] and the `encode_to` functions write a value.
+#[derive(Debug, Clone, Default)]
+pub struct DecodeOptions {
+    /// What to write for a NaN or infinite number. The default fails.
+    pub non_finite: NonFinite,
+    /// The order of the entries of every object, nested ones included.
+    /// Sorting needs the entries of each object on the path to the value
+    /// being written, so memory use is no longer bounded by the depth alone.
+    pub key_order: KeyOrder,
+    /// Characters to escape in strings and keys. The default escapes only
+    /// those JSON requires.
+    pub escape: Escape,
+}
+
+/// The options of the functions that cannot fail.
+pub(crate) const LOSSY: DecodeOptions = DecodeOptions {
+    non_finite: NonFinite::Null,
+    key_order: KeyOrder::Document,
+    escape: Escape {
+        ascii_only: false,
+        html_safe: false,
+    },
+};
+
+/// Encodes `json` as compact JSON written as `options` say.
+pub fn decode_with(json: JsoncSlice, options: &DecodeOptions) -> Result<String> {
     let mut result = String::new();
-    let iter = json.nodes.iter();
-    let mut iter_str = json.strings.iter();
-    let mut iter_num = json.numbers.iter();
-    for node in iter {
-        match node {
-            Node::StartArray => {
-                result.push('[');
+    encode_to_fmt_with(json, &mut result, options)?;
+    Ok(result)
+}
+
+/// Encodes `json` as compact JSON to `writer`, piece by piece as the value is
+/// walked, so memory use grows only with the nesting depth. The writes are
+/// small, so an unbuffered writer such as a file is best wrapped in a
+/// [`std::io::BufWriter`].
+///
+/// This uses the default [`DecodeOptions`], so a NaN or infinite number
+/// fails.
+pub fn encode_to<W: io::Write + ?Sized>(json: JsoncSlice, writer: &mut W) -> Result<()> {
+    encode_to_with(json, writer, &DecodeOptions::default())
+}
+
+/// Like [`encode_to`], written as `options` say.
+pub fn encode_to_with<W: io::Write + ?Sized>(
+    json: JsoncSlice,
+    writer: &mut W,
+    options: &DecodeOptions,
+) -> Result<()> {
+    encode(&mut IoSink(writer), json, options)
+}
+
+/// Like [`encode_to`], writing to a [`fmt::Write`] such as a `String` or a
+/// [`fmt::Formatter`].
+pub fn encode_to_fmt<W: fmt::Write + ?Sized>(json: JsoncSlice, writer: &mut W) -> Result<()> {
+    encode_to_fmt_with(json, writer, &DecodeOptions::default())
+}
+
+/// Like [`encode_to_fmt`], written as `options` say.
+pub fn encode_to_fmt_with<W: fmt::Write + ?Sized>(
+    json: JsoncSlice,
+    writer: &mut W,
+    options: &DecodeOptions,
+) -> Result<()> {
+    encode(&mut FmtSink(writer), json, options)
+}
+
+fn encode<S: Sink>(sink: &mut S, json: JsoncSlice, options: &DecodeOptions) -> Result<()> {
+    match options.key_order {
+        KeyOrder::Document => walk(json, &mut Encoder::new(sink, options)),
+        _ => write_sorted(sink, json, options),
+    }
+}
+
+/// Writes `json` with the entries of its objects in `options.key_order`.
+fn write_sorted<S: Sink + ?Sized>(
+    sink: &mut S,
+    json: JsoncSlice,
+    options: &DecodeOptions,
+) -> Result<()> {
+    match json.kind() {
+        Some(ValueKind::Object) => {
+            sink.write_str("{")?;
+            for (i, (key, value)) in options.key_order.entries(json).into_iter().enumerate() {
+                if i > 0 {
+                    sink.write_str(",")?;
+                }
+                write_string(sink, key, options.escape)?;
+                sink.write_str(":")?;
+                write_sorted(sink, value, options)?;
             }
-            Node::EndArray => {
-                if result.ends_with(',') {
-                    result.pop();
+            sink.write_str("}")
+        }
+        Some(ValueKind::Array) => {
+            sink.write_str("[")?;
+            for (i, element) in json.array_iter().unwrap().enumerate() {
+                if i > 0 {
+                    sink.write_str(",")?;
                 }
-                result.push_str("],");
+                write_sorted(sink, element, options)?;
             }
-            Node::StartObject => {
-                result.push('{');
+            sink.write_str("]")
+        }
+        _ => write_scalar(sink, json, options),
+    }
+}
+
+/// Where an encoder writes its output.
+pub(crate) trait Sink {
+    fn write_str(&mut self, s: &str) -> Result<()>;
+
+    fn write_fmt(&mut self, args: fmt::Arguments) -> Result<()>;
+}
+
+impl<S: Sink + ?Sized> Sink for &mut S {
+    fn write_str(&mut self, s: &str) -> Result<()> {
+        (**self).write_str(s)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments) -> Result<()> {
+        (**self).write_fmt(args)
+    }
+}
+
+pub(crate) struct IoSink<'w, W: ?Sized>(pub(crate) &'w mut W);
+
+impl<W: io::Write + ?Sized> Sink for IoSink<'_, W> {
+    fn write_str(&mut self, s: &str) -> Result<()> {
+        Ok(self.0.write_all(s.as_bytes())?)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments) -> Result<()> {
+        Ok(self.0.write_fmt(args)?)
+    }
+}
+
+pub(crate) struct FmtSink<'w, W: ?Sized>(pub(crate) &'w mut W);
+
+impl<W: fmt::Write + ?Sized> Sink for FmtSink<'_, W> {
+    fn write_str(&mut self, s: &str) -> Result<()> {
+        Ok(self.0.write_str(s)?)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments) -> Result<()> {
+        Ok(self.0.write_fmt(args)?)
+    }
+}
+
+struct Encoder<'o, S> {
+    sink: S,
+    options: &'o DecodeOptions,
+    /// Whether a value has been written at the current level, so the next one
+    /// needs a separator.
+    need_comma: bool,
+}
+
+impl<'o, S: Sink> Encoder<'o, S> {
+    fn new(sink: S, options: &'o DecodeOptions) -> Self {
+        Self {
+            sink,
+            options,
+            need_comma: false,
+        }
+    }
+
+    fn separate(&mut self) -> Result<()> {
+        if self.need_comma {
+            self.sink.write_str(",")?;
+        }
+        Ok(())
+    }
+
+    /// Writes a scalar or the bracket that opens or closes a container.
+    fn token(&mut self, token: &str, need_comma: bool) -> Result<()> {
+        if !token.starts_with([']', '}']) {
+            self.separate()?;
+        }
+        self.sink.write_str(token)?;
+        self.need_comma = need_comma;
+        Ok(())
+    }
+}
+
+impl<'a, S: Sink> Visitor<'a> for Encoder<'_, S> {
+    type Error = Error;
+
+    fn start_object(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<()> {
+        self.token("{", false)
+    }
+
+    fn end_object(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<()> {
+        self.token("}", true)
+    }
+
+    fn start_array(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<()> {
+        self.token("[", false)
+    }
+
+    fn end_array(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<()> {
+        self.token("]", true)
+    }
+
+    fn key(&mut self, key: &'a str, _path: &[PathItem<'a>], _depth: usize) -> Result<()> {
+        self.separate()?;
+        write_string(&mut self.sink, key, self.options.escape)?;
+        self.sink.write_str(":")?;
+        self.need_comma = false;
+        Ok(())
+    }
+
+    fn string(&mut self, value: &'a str, _path: &[PathItem<'a>], _depth: usize) -> Result<()> {
+        self.separate()?;
+        write_string(&mut self.sink, value, self.options.escape)?;
+        self.need_comma = true;
+        Ok(())
+    }
+
+    fn number(&mut self, value: f64, _path: &[PathItem<'a>], _depth: usize) -> Result<()> {
+        self.separate()?;
+        write_number(&mut self.sink, value, self.options.non_finite)?;
+        self.need_comma = true;
+        Ok(())
+    }
+
+    fn bool(&mut self, value: bool, _path: &[PathItem<'a>], _depth: usize) -> Result<()> {
+        self.token(if value { "true" } else { "false" }, true)
+    }
+
+    fn null(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<()> {
+        self.token("null", true)
+    }
+}
+
+/// Writes `value` as a quoted JSON string, escaping `"`, `\`, control
+/// characters and whatever else `escape` asks for. Runs of characters that
+/// need no escape are written as they are, in one piece.
+pub(crate) fn write_string<S: Sink + ?Sized>(
+    sink: &mut S,
+    value: &str,
+    escape: Escape,
+) -> Result<()> {
+    sink.write_str("\"")?;
+    let mut run_start = 0;
+    for (i, c) in value.char_indices() {
+        let short = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{8}' => "\\b",
+            '\u{c}' => "\\f",
+            '\0'..='\u{1f}' => "",
+            '<' | '>' | '&' | '\u{2028}' | '\u{2029}' if escape.html_safe => "",
+            _ if escape.ascii_only && !c.is_ascii() => "",
+            _ => continue,
+        };
+        sink.write_str(&value[run_start..i])?;
+        if short.is_empty() {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                sink.write_fmt(format_args!("\\u{:04x}", unit))?;
             }
-            Node::EndObject => {
-                if result.ends_with(',') {
-                    result.pop();
+        } else {
+            sink.write_str(short)?;
+        }
+        run_start = i + c.len_utf8();
+    }
+    sink.write_str(&value[run_start..])?;
+    sink.write_str("\"")
+}
+
+/// Compact JSON, or with `{:#}` JSON laid out by the default
+/// [`PrettyOptions`]. A NaN or infinite number is written as `null`.
+/// Columns that do not hold a well-formed value are written as they are, as
+/// [`Debug`](fmt::Debug) shows them.
+impl fmt::Display for JsoncSlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(*self, "JsoncSlice", NonFinite::Null, f)
+    }
+}
+
+impl fmt::Display for Jsonc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(self.as_slice(), "Jsonc", NonFinite::Null, f)
+    }
+}
+
+/// The value as JSON, as [`Display`](fmt::Display) writes it but with the
+/// literals `NaN`, `Infinity` and `-Infinity` in place of `null`, so `{:#?}`
+/// shows it as an indented tree and a NaN never looks like a `null`.
+/// Columns that do not hold a well-formed value are shown as they are.
+impl fmt::Debug for JsoncSlice<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        debug(*self, "JsoncSlice", f)
+    }
+}
+
+impl fmt::Debug for Jsonc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        debug(self.as_slice(), "Jsonc", f)
+    }
+}
+
+fn display(
+    json: JsoncSlice,
+    name: &str,
+    non_finite: NonFinite,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    if !is_well_formed(json) {
+        return columns(json, name, f);
+    }
+    if f.alternate() {
+        let options = PrettyOptions {
+            non_finite,
+            ..PrettyOptions::default()
+        };
+        f.write_str(&decode_pretty(json, &options))
+    } else {
+        let options = DecodeOptions {
+            non_finite,
+            ..LOSSY
+        };
+        encode_to_fmt_with(json, f, &options).map_err(|_| fmt::Error)
+    }
+}
+
+fn debug(json: JsoncSlice, name: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if !is_well_formed(json) {
+        return columns(json, name, f);
+    }
+    f.debug_tuple(name).field(&Literals(json)).finish()
+}
+
+fn columns(json: JsoncSlice, name: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct(name)
+        .field("nodes", &json.nodes)
+        .field("strings", &json.strings)
+        .field("numbers", &json.numbers)
+        .finish()
+}
+
+/// A well-formed value written as JSON with non-finite numbers as literals.
+struct Literals<'a>(JsoncSlice<'a>);
+
+impl fmt::Debug for Literals<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(self.0, "", NonFinite::Literal, f)
+    }
+}
+
+/// Whether `json` can be walked as one value: its brackets match, every
+/// object entry is a key followed by a value, there is exactly one value at
+/// the top level, and the string and number columns are long enough.
+fn is_well_formed(json: JsoncSlice) -> bool {
+    let mut open = Vec::new();
+    let (mut values, mut strings, mut numbers) = (0, 0, 0);
+    // Whether the last node was a key, which a value must follow.
+    let mut after_key = false;
+    for node in json.nodes {
+        match node {
+            Node::Key => {
+                if after_key || open.last() != Some(&Node::StartObject) {
+                    return false;
                 }
-                result.push_str("},");
+                after_key = true;
+                strings += 1;
+                continue;
             }
-            Node::Key => {
-                let key = iter_str.next().unwrap();
-                result.push_str(&format!("\"{}\":", key));
+            Node::EndArray | Node::EndObject => {
+                let start = match node {
+                    Node::EndArray => Node::StartArray,
+                    _ => Node::StartObject,
+                };
+                if after_key || open.pop() != Some(start) {
+                    return false;
+                }
+                continue;
             }
-            Node::String => {
-                let string = iter_str.next().unwrap();
-                let bytes = string.as_bytes();
-                let mut escaped_string = String::new();
-                escape_scalar_string(bytes, 0, bytes.len(), &mut escaped_string);
-                result.push_str(&escaped_string);
-                result.push(',');
+            _ => {}
+        }
+        if open.last() == Some(&Node::StartObject) && !after_key {
+            return false;
+        }
+        after_key = false;
+        if open.is_empty() {
+            values += 1;
+        }
+        match node {
+            Node::StartArray | Node::StartObject => open.push(*node),
+            Node::String => strings += 1,
+            Node::Number => numbers += 1,
+            _ => {}
+        }
+    }
+    open.is_empty() && values == 1 && strings <= json.strings.len() && numbers <= json.numbers.len()
+}
+
+/// Writes `n` with the fewest digits that read back as the same `f64`, in
+/// exponent notation such as `1e300` or `2.5e-8` where that is shorter.
+/// Integers below 2^53 are always written in full.
+fn write_number<S: Sink + ?Sized>(sink: &mut S, n: f64, non_finite: NonFinite) -> Result<()> {
+    if !n.is_finite() {
+        return match non_finite {
+            NonFinite::Error => Err(Error::NonFiniteNumber(n)),
+            NonFinite::Null => sink.write_str("null"),
+            NonFinite::Literal if n.is_nan() => sink.write_str("NaN"),
+            NonFinite::Literal if n > 0.0 => sink.write_str("Infinity"),
+            NonFinite::Literal => sink.write_str("-Infinity"),
+        };
+    }
+    let mut plain = NumberBuf::default();
+    let mut exp = NumberBuf::default();
+    // The exponent form is never longer than `-1.2345678901234567e-308`.
+    write!(exp, "{:e}", n).unwrap();
+    let is_safe_integer = n.fract() == 0.0 && n.abs() < (1u64 << 53) as f64;
+    if write!(plain, "{}", n).is_ok() && (is_safe_integer || plain.len <= exp.len) {
+        sink.write_str(plain.as_str())
+    } else {
+        sink.write_str(exp.as_str())
+    }
+}
+
+/// A buffer on the stack for formatting a number, which fails rather than
+/// grow.
+#[derive(Default)]
+struct NumberBuf {
+    bytes: [u8; 32],
+    len: usize,
+}
+
+impl NumberBuf {
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len]).unwrap()
+    }
+}
+
+impl fmt::Write for NumberBuf {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.bytes
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// The indentation for one level of nesting in [`decode_pretty`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Indent {
+    Spaces(usize),
+    Tabs(usize),
+}
+
+/// The line break that [`decode_pretty`] writes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Newline {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+/// How [`decode_pretty`] lays out a value. The default indents by two spaces
+/// and writes `"key": value`, with one array element per line.
+#[derive(Debug, Clone)]
+pub struct PrettyOptions {
+    /// The indentation for each level of nesting.
+    pub indent: Indent,
+    /// The line break written between lines.
+    pub newline: Newline,
+    /// Whether to write a space between a key's colon and its value.
+    pub space_after_colon: bool,
+    /// Whether to write a non-empty array of scalars on one line, as
+    /// `[1, 2, 3]`.
+    pub compact_scalar_arrays: bool,
+    /// The longest line, in characters, that a compact array may make, with
+    /// a tab counting as one character. An array that does not fit has one
+    /// element per line instead. Other lines are never wrapped, and this has
+    /// no effect unless `compact_scalar_arrays` is set.
+    pub compact_array_max_width: Option<usize>,
+    /// The order of the entries of every object, nested ones included.
+    pub key_order: KeyOrder,
+    /// Characters to escape in strings and keys.
+    pub escape: Escape,
+    /// What to write for a NaN or infinite number. The default writes
+    /// `null`. [`decode_pretty`] cannot fail, so it writes `null` in place
+    /// of [`NonFinite::Error`]; see [`decode_pretty_with`].
+    pub non_finite: NonFinite,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self {
+            indent: Indent::Spaces(2),
+            newline: Newline::Lf,
+            space_after_colon: true,
+            compact_scalar_arrays: false,
+            compact_array_max_width: None,
+            key_order: KeyOrder::Document,
+            escape: Escape::default(),
+            non_finite: NonFinite::Null,
+        }
+    }
+}
+
+/// Encodes `json` as indented JSON laid out by `options`, without a final
+/// line break. A NaN or infinite number is written as `null`, unless
+/// `options.non_finite` is [`NonFinite::Literal`].
+pub fn decode_pretty(json: JsoncSlice, options: &PrettyOptions) -> String {
+    let non_finite = match options.non_finite {
+        NonFinite::Error => NonFinite::Null,
+        non_finite => non_finite,
+    };
+    pretty(json, options, non_finite).expect("writing to a String cannot fail")
+}
+
+/// Like [`decode_pretty`], failing with [`Error::NonFiniteNumber`] on a NaN
+/// or infinite number if `options.non_finite` says so.
+pub fn decode_pretty_with(json: JsoncSlice, options: &PrettyOptions) -> Result<String> {
+    pretty(json, options, options.non_finite)
+}
+
+fn pretty(json: JsoncSlice, options: &PrettyOptions, non_finite: NonFinite) -> Result<String> {
+    let mut pretty = Pretty {
+        options,
+        scalars: DecodeOptions {
+            non_finite,
+            escape: options.escape,
+            ..LOSSY
+        },
+        result: String::new(),
+        line_start: 0,
+    };
+    pretty.value(json, 0, false)?;
+    Ok(pretty.result)
+}
+
+struct Pretty<'o> {
+    options: &'o PrettyOptions,
+    /// How to write keys and scalars.
+    scalars: DecodeOptions,
+    result: String,
+    /// Where the current line starts in `result`.
+    line_start: usize,
+}
+
+impl Pretty<'_> {
+    /// Writes `json`, whose line ends with a comma if `comma_follows`.
+    fn value(&mut self, json: JsoncSlice, depth: usize, comma_follows: bool) -> Result<()> {
+        match json.kind() {
+            Some(ValueKind::Array) => self.array(json, depth, comma_follows),
+            Some(ValueKind::Object) => self.object(json, depth),
+            _ => self.scalar(json),
+        }
+    }
+
+    fn array(&mut self, json: JsoncSlice, depth: usize, comma_follows: bool) -> Result<()> {
+        if json.is_empty() == Some(true) {
+            self.result.push_str("[]");
+            return Ok(());
+        }
+        if self.options.compact_scalar_arrays && json.array_iter().unwrap().all(is_scalar) {
+            let start = self.result.len();
+            self.result.push('[');
+            for (i, element) in json.array_iter().unwrap().enumerate() {
+                if i > 0 {
+                    self.result.push_str(", ");
+                }
+                self.scalar(element)?;
             }
-            Node::Number => {
-                let number = iter_num.next().unwrap();
-                result.push_str(&format!("{},", number));
+            self.result.push(']');
+            let width = self.result[self.line_start..].chars().count() + comma_follows as usize;
+            if self
+                .options
+                .compact_array_max_width
+                .map_or(true, |max| width <= max)
+            {
+                return Ok(());
             }
-            Node::True => {
-                result.push_str("true,");
+            self.result.truncate(start);
+        }
+        self.result.push('[');
+        let mut elements = json.array_iter().unwrap().peekable();
+        while let Some(element) = elements.next() {
+            let last = elements.peek().is_none();
+            self.newline(depth + 1);
+            self.value(element, depth + 1, !last)?;
+            if !last {
+                self.result.push(',');
             }
-            Node::False => {
-                result.push_str("false,");
+        }
+        self.newline(depth);
+        self.result.push(']');
+        Ok(())
+    }
+
+    fn object(&mut self, json: JsoncSlice, depth: usize) -> Result<()> {
+        if json.is_empty() == Some(true) {
+            self.result.push_str("{}");
+            return Ok(());
+        }
+        self.result.push('{');
+        let mut entries = self.options.key_order.entries(json).into_iter().peekable();
+        while let Some((key, value)) = entries.next() {
+            let last = entries.peek().is_none();
+            self.newline(depth + 1);
+            write_string(&mut FmtSink(&mut self.result), key, self.options.escape)?;
+            self.result.push(':');
+            if self.options.space_after_colon {
+                self.result.push(' ');
             }
-            Node::Null => {
-                result.push_str("null,");
+            self.value(value, depth + 1, !last)?;
+            if !last {
+                self.result.push(',');
             }
         }
+        self.newline(depth);
+        self.result.push('}');
+        Ok(())
     }
-    if result.ends_with(',') {
-        result.pop();
+
+    fn scalar(&mut self, json: JsoncSlice) -> Result<()> {
+        write_scalar(&mut FmtSink(&mut self.result), json, &self.scalars)
+    }
+
+    /// Ends the line and indents the next one to `depth`.
+    fn newline(&mut self, depth: usize) {
+        self.result.push_str(match self.options.newline {
+            Newline::Lf => "\n",
+            Newline::CrLf => "\r\n",
+        });
+        self.line_start = self.result.len();
+        let (c, width) = match self.options.indent {
+            Indent::Spaces(width) => (' ', width),
+            Indent::Tabs(width) => ('\t', width),
+        };
+        self.result.extend(std::iter::repeat(c).take(depth * width));
+    }
+}
+
+fn is_scalar(json: JsoncSlice) -> bool {
+    !matches!(
+        json.kind(),
+        None | Some(ValueKind::Array | ValueKind::Object)
+    )
+}
+
+pub(crate) fn write_scalar<S: Sink + ?Sized>(
+    sink: &mut S,
+    json: JsoncSlice,
+    options: &DecodeOptions,
+) -> Result<()> {
+    match json.kind() {
+        Some(ValueKind::String) => write_string(sink, json.as_str().unwrap(), options.escape),
+        Some(ValueKind::Number) => write_number(sink, json.as_f64().unwrap(), options.non_finite),
+        Some(ValueKind::Bool) => sink.write_str(if json.as_bool().unwrap() {
+            "true"
+        } else {
+            "false"
+        }),
+        _ => sink.write_str("null"),
     }
-    result
 }
@@ -0,0 +1,59 @@
+use std::{fmt, io};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A byte in a node column that does not name any `Node` variant.
+    InvalidNode(u8),
+    /// Malformed JSON text or a malformed path or query expression, with the
+    /// byte offset where it stops making sense.
+    Syntax { msg: String, pos: usize },
+    /// An expression that cannot be applied to the value it runs on, such as
+    /// indexing a number with a key.
+    Eval(String),
+    /// A NaN or infinite number, which JSON cannot represent.
+    NonFiniteNumber(f64),
+    /// An object key that appears more than once where keys must be unique.
+    DuplicateKey(String),
+    /// A failure of the writer that JSON is encoded to.
+    Io(io::Error),
+    /// A failure of the formatter that JSON is encoded to.
+    Fmt(fmt::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidNode(tag) => write!(f, "invalid node tag {}", tag),
+            Error::Syntax { msg, pos } => write!(f, "{} at position {}", msg, pos),
+            Error::Eval(msg) => f.write_str(msg),
+            Error::NonFiniteNumber(n) => write!(f, "{} cannot be represented in JSON", n),
+            Error::DuplicateKey(key) => write!(f, "duplicate key {:?}", key),
+            Error::Io(err) => write!(f, "write failed: {}", err),
+            Error::Fmt(err) => write!(f, "write failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Fmt(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<fmt::Error> for Error {
+    fn from(err: fmt::Error) -> Self {
+        Error::Fmt(err)
+    }
+}
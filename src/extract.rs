@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::path::{IntoJsonPath, JsonPath, PathSegment};
+use crate::slice::JsoncSlice;
+use crate::value::{Jsonc, Node};
+
+impl<'a> JsoncSlice<'a> {
+    /// Looks up many paths at once, returning what each one finds in the
+    /// order of `paths`.
+    ///
+    /// Where calling [`JsoncSlice::get_json_path`] for each path rescans the
+    /// value every time, this reads the node column once and matches all the
+    /// paths together, stopping as soon as every path is found. A step that
+    /// counts from the end of an array is taken when the array closes, by
+    /// looking up the rest of its paths in the chosen element. As with
+    /// `get_json_path`, the first entry of a repeated key wins.
+    ///
+    /// To run the same paths against many values, build a [`CompiledQuery`]
+    /// once instead.
+    pub fn extract_many(&self, paths: &[JsonPath]) -> Vec<Option<JsoncSlice<'a>>> {
+        CompiledQuery::new(paths).eval(*self)
+    }
+}
+
+impl Jsonc {
+    /// See [`JsoncSlice::extract_many`].
+    pub fn extract_many(&self, paths: &[JsonPath]) -> Vec<Option<JsoncSlice>> {
+        self.as_slice().extract_many(paths)
+    }
+}
+
+/// A set of paths planned once and then run against any number of values,
+/// as [`JsoncSlice::extract_many`] does.
+///
+/// The paths are merged into a trie so that paths with a common prefix
+/// share its steps, and the keys are hashed up front, so matching a key in
+/// a value costs one hash of that key and a table lookup. `Jsonc` has no
+/// key index, so every evaluation still reads the value's nodes in order.
+///
+/// A `CompiledQuery` is `Send` and `Sync`, so one can be shared by many
+/// worker threads.
+#[derive(Debug, Clone)]
+pub struct CompiledQuery {
+    nodes: Vec<TrieNode>,
+    len: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    /// The paths that end here, by their position in the list of paths.
+    targets: Vec<usize>,
+    /// The children reached by a key, by the hash of the key.
+    keys: KeyTable,
+    indexes: HashMap<usize, usize>,
+    from_end: Vec<(usize, usize)>,
+}
+
+type KeyTable = HashMap<u64, Vec<(Box<str>, usize)>, BuildHasherDefault<Prehashed>>;
+
+/// Hashes a key with 64-bit FNV-1a, which is quick for the short strings
+/// that object keys usually are.
+fn hash_key(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// A hasher for tables keyed by hashes that were already computed.
+#[derive(Default)]
+struct Prehashed(u64);
+
+impl Hasher for Prehashed {
+    fn write(&mut self, _bytes: &[u8]) {
+        unreachable!("only u64 keys are hashed")
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.0 = hash;
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// The position of a node and of the next string and number in the columns.
+#[derive(Debug, Clone, Copy, Default)]
+struct Position {
+    node: usize,
+    string: usize,
+    number: usize,
+}
+
+/// A container being read, with the trie node it matched, if any.
+struct Frame {
+    trie: Option<usize>,
+    start: Position,
+    is_array: bool,
+    len: usize,
+    /// Where each element starts, kept only when an element may be chosen by
+    /// counting from the end.
+    elements: Option<Vec<Position>>,
+    /// The trie nodes matched by the keys of an object so far, so that only
+    /// the first entry of a repeated key is followed.
+    matched_keys: Vec<usize>,
+}
+
+/// What the paths found so far, and how many are still missing.
+struct Found<'a> {
+    values: Vec<Option<JsoncSlice<'a>>>,
+    missing: usize,
+}
+
+impl CompiledQuery {
+    pub fn new(paths: &[JsonPath]) -> Self {
+        let mut query = Self {
+            nodes: vec![TrieNode::default()],
+            len: paths.len(),
+        };
+        for (target, path) in paths.iter().enumerate() {
+            let mut node = 0;
+            for segment in path.segments() {
+                node = query.child(node, segment);
+            }
+            query.nodes[node].targets.push(target);
+        }
+        query
+    }
+
+    /// Parses each path in the [`JsonPath`] syntax and compiles them together.
+    pub fn parse<P: IntoJsonPath>(paths: impl IntoIterator<Item = P>) -> Result<Self> {
+        let paths = paths
+            .into_iter()
+            .map(IntoJsonPath::into_json_path)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::new(&paths))
+    }
+
+    /// The number of paths in the query.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Looks up every path in `json`, returning what each one finds in the
+    /// order the paths were given.
+    pub fn eval<'a>(&self, json: impl Into<JsoncSlice<'a>>) -> Vec<Option<JsoncSlice<'a>>> {
+        let mut found = Found {
+            values: vec![None; self.len],
+            missing: self.len,
+        };
+        self.extract(0, json.into(), &mut found);
+        found.values
+    }
+
+    fn child(&mut self, node: usize, segment: &PathSegment) -> usize {
+        let next = self.nodes.len();
+        let trie_node = &mut self.nodes[node];
+        let child = match segment {
+            PathSegment::Key(key) => {
+                let entries = trie_node.keys.entry(hash_key(key)).or_default();
+                match entries.iter().find(|(entry, _)| **entry == **key) {
+                    Some(&(_, child)) => child,
+                    None => {
+                        entries.push((key.as_str().into(), next));
+                        next
+                    }
+                }
+            }
+            PathSegment::Index(idx) => *trie_node.indexes.entry(*idx).or_insert(next),
+            PathSegment::IndexFromEnd(idx) => {
+                match trie_node
+                    .from_end
+                    .iter()
+                    .find(|(from_end, _)| from_end == idx)
+                {
+                    Some(&(_, child)) => child,
+                    None => {
+                        trie_node.from_end.push((*idx, next));
+                        next
+                    }
+                }
+            }
+        };
+        if child == next {
+            self.nodes.push(TrieNode::default());
+        }
+        child
+    }
+
+    fn key_child(&self, trie: usize, key: &str) -> Option<usize> {
+        let entries = self.nodes[trie].keys.get(&hash_key(key))?;
+        entries
+            .iter()
+            .find(|(entry, _)| **entry == *key)
+            .map(|&(_, child)| child)
+    }
+
+    /// Matches the paths below trie node `root` against `json`.
+    fn extract<'a>(&self, root: usize, json: JsoncSlice<'a>, found: &mut Found<'a>) {
+        let mut stack: Vec<Frame> = Vec::new();
+        let mut pos = Position::default();
+        let mut after_key = None;
+        while pos.node < json.nodes.len() && found.missing > 0 {
+            let node = json.nodes[pos.node];
+            match node {
+                Node::Key => {
+                    let frame = stack.last_mut().unwrap();
+                    after_key = frame
+                        .trie
+                        .filter(|&trie| !self.nodes[trie].keys.is_empty())
+                        .and_then(|trie| self.key_child(trie, &json.strings[pos.string]))
+                        .filter(|child| !frame.matched_keys.contains(child));
+                    frame.matched_keys.extend(after_key);
+                    pos.node += 1;
+                    pos.string += 1;
+                    continue;
+                }
+                Node::EndArray | Node::EndObject => {
+                    let frame = stack.pop().unwrap();
+                    let last_end = pos;
+                    pos.node += 1;
+                    if let Some(trie) = frame.trie {
+                        self.record(trie, sub_slice(json, frame.start, pos), found);
+                        if let Some(elements) = frame.elements {
+                            self.extract_from_end(trie, json, &elements, last_end, found);
+                        }
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            let trie = match stack.last_mut() {
+                None => Some(root),
+                Some(frame) if frame.is_array => {
+                    let idx = frame.len;
+                    frame.len += 1;
+                    if let Some(elements) = &mut frame.elements {
+                        elements.push(pos);
+                    }
+                    frame
+                        .trie
+                        .and_then(|trie| self.nodes[trie].indexes.get(&idx).copied())
+                }
+                Some(_) => after_key.take(),
+            };
+            let start = pos;
+            pos.node += 1;
+            match node {
+                Node::StartArray | Node::StartObject => {
+                    let from_end = trie.is_some_and(|trie| !self.nodes[trie].from_end.is_empty());
+                    stack.push(Frame {
+                        trie,
+                        start,
+                        is_array: node == Node::StartArray,
+                        len: 0,
+                        elements: (from_end && node == Node::StartArray).then(Vec::new),
+                        matched_keys: Vec::new(),
+                    });
+                    continue;
+                }
+                Node::String => pos.string += 1,
+                Node::Number => pos.number += 1,
+                _ => {}
+            }
+            if let Some(trie) = trie {
+                self.record(trie, sub_slice(json, start, pos), found);
+            }
+        }
+    }
+
+    /// Takes the steps of trie node `trie` that count from the end of an
+    /// array whose elements start at `elements` and which closes at `end`.
+    fn extract_from_end<'a>(
+        &self,
+        trie: usize,
+        json: JsoncSlice<'a>,
+        elements: &[Position],
+        end: Position,
+        found: &mut Found<'a>,
+    ) {
+        for &(from_end, child) in &self.nodes[trie].from_end {
+            if from_end == 0 || from_end > elements.len() {
+                continue;
+            }
+            let idx = elements.len() - from_end;
+            let element_end = elements.get(idx + 1).copied().unwrap_or(end);
+            self.extract(child, sub_slice(json, elements[idx], element_end), found);
+        }
+    }
+
+    fn record<'a>(&self, trie: usize, value: JsoncSlice<'a>, found: &mut Found<'a>) {
+        for &target in &self.nodes[trie].targets {
+            if found.values[target].is_none() {
+                found.values[target] = Some(value);
+                found.missing -= 1;
+            }
+        }
+    }
+}
+
+fn sub_slice(json: JsoncSlice, start: Position, end: Position) -> JsoncSlice {
+    JsoncSlice {
+        nodes: &json.nodes[start.node..end.node],
+        strings: &json.strings[start.string..end.string],
+        numbers: &json.numbers[start.number..end.number],
+    }
+}
+
+/// A single path planned once and then looked up in any number of values.
+/// See [`CompiledQuery`].
+#[derive(Debug, Clone)]
+pub struct CompiledPath {
+    query: CompiledQuery,
+}
+
+impl CompiledPath {
+    pub fn new(path: &JsonPath) -> Self {
+        Self {
+            query: CompiledQuery::new(std::slice::from_ref(path)),
+        }
+    }
+
+    pub fn parse<P: IntoJsonPath>(path: P) -> Result<Self> {
+        Ok(Self::new(&path.into_json_path()?))
+    }
+
+    /// Looks up the path in `json` like [`JsoncSlice::get_json_path`].
+    pub fn eval<'a>(&self, json: impl Into<JsoncSlice<'a>>) -> Option<JsoncSlice<'a>> {
+        self.query.eval(json).pop().flatten()
+    }
+}
+
+impl FromStr for CompiledPath {
+    type Err = Error;
+
+    fn from_str(path: &str) -> Result<Self> {
+        Self::parse(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode_slice;
+    use crate::parser::parse_value;
+
+    #[test]
+    fn test_extract_many() {
+        let json =
+            parse_value(br#"{"a":{"b":[1,{"c":"x"},[true,null]]},"d":2.5,"a":{"e":0},"":"empty"}"#);
+        let paths: Vec<JsonPath> = [
+            "a.b[1].c",
+            "a.b[-1][-2]",
+            "a.b[-3]",
+            "a.b",
+            "d",
+            "a.e",
+            "a.b[3]",
+            "a.b[-4]",
+            "d.x",
+            r#"[""]"#,
+            "",
+            "a.b[1].c",
+        ]
+        .iter()
+        .map(|path| JsonPath::parse(path).unwrap())
+        .collect();
+        let found: Vec<_> = json
+            .extract_many(&paths)
+            .into_iter()
+            .map(|value| value.map(decode_slice))
+            .collect();
+        let expected: Vec<_> = paths
+            .iter()
+            .map(|path| json.as_slice().get_json_path(path).map(decode_slice))
+            .collect();
+        assert_eq!(found, expected);
+        assert_eq!(found[1].as_deref(), Some("true"));
+        assert_eq!(found[5], None);
+    }
+
+    #[test]
+    fn test_compiled_query() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<CompiledQuery>();
+        assert_send_sync::<CompiledPath>();
+
+        let query = CompiledQuery::parse(["user.lang", "tags[-1]", "user.id"]).unwrap();
+        assert_eq!(query.len(), 3);
+        let documents = [
+            parse_value(br#"{"user":{"id":1,"lang":"ja"},"tags":["a","b"]}"#),
+            parse_value(br#"{"tags":[],"user":{"lang":"en"}}"#),
+        ];
+        let found: Vec<Vec<_>> = documents
+            .iter()
+            .map(|json| {
+                query
+                    .eval(json)
+                    .into_iter()
+                    .map(|value| value.map(decode_slice))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                vec![
+                    Some(r#""ja""#.to_string()),
+                    Some(r#""b""#.to_string()),
+                    Some("1".to_string())
+                ],
+                vec![Some(r#""en""#.to_string()), None, None],
+            ]
+        );
+
+        let path: CompiledPath = "user.lang".parse().unwrap();
+        let lang = std::thread::scope(|scope| {
+            let handle = scope.spawn(|| path.eval(&documents[0]).and_then(|v| v.as_str()));
+            handle.join().unwrap()
+        });
+        assert_eq!(lang, Some("ja"));
+        assert!(CompiledPath::parse("user[").is_err());
+    }
+}
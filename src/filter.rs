@@ -0,0 +1,534 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::jsonpath::Parser;
+use crate::path::JsonPath;
+use crate::slice::{JsoncSlice, ValueKind};
+use crate::value::{Jsonc, Node};
+
+/// A row filter such as
+/// `$.retweet_count > 100 && $.user.lang == "en" && exists($.entities.urls[0])`.
+///
+/// Operands are paths starting with `$`, in the [`JsonPath`] syntax, and
+/// literals: numbers, strings in single or double quotes, `true`, `false`
+/// and `null`. A predicate is one of:
+///
+/// - `a == b`, `a != b`, `a < b`, `a <= b`, `a > b` or `a >= b`,
+/// - `a in (x, y, ...)` with a list of literals,
+/// - `a like "pattern"`, where `%` matches any run of characters, `_` any
+///   one character and `\` escapes the next one; the pattern is a string
+///   literal, so that backslash is itself written `\\`,
+/// - `a is null`, `a is not null`, `a is missing` or `a is not missing`,
+/// - `exists($.path)`, the same as `$.path is not missing`,
+/// - or an operand on its own, which must be `true` or `false`.
+///
+/// Predicates combine with `&&`, `||`, `!` and parentheses.
+///
+/// Evaluation uses three-valued logic as SQL does. A comparison is
+/// [`Truth::Unknown`] when an operand is missing or null, or when the
+/// operands have different types; only `==` and `!=` compare arrays and
+/// objects. `is null`, `is missing` and `exists` are never unknown.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+/// The result of a [`Filter`]: true, false, or unknown for lack of data.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Truth {
+    True,
+    False,
+    Unknown,
+}
+
+impl Truth {
+    fn and(self, other: Truth) -> Truth {
+        match (self, other) {
+            (Truth::False, _) | (_, Truth::False) => Truth::False,
+            (Truth::True, Truth::True) => Truth::True,
+            _ => Truth::Unknown,
+        }
+    }
+
+    fn or(self, other: Truth) -> Truth {
+        match (self, other) {
+            (Truth::True, _) | (_, Truth::True) => Truth::True,
+            (Truth::False, Truth::False) => Truth::False,
+            _ => Truth::Unknown,
+        }
+    }
+
+    fn not(self) -> Truth {
+        match self {
+            Truth::True => Truth::False,
+            Truth::False => Truth::True,
+            Truth::Unknown => Truth::Unknown,
+        }
+    }
+}
+
+impl From<bool> for Truth {
+    fn from(b: bool) -> Truth {
+        if b {
+            Truth::True
+        } else {
+            Truth::False
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CompareOp, Operand),
+    In(Operand, Vec<Jsonc>),
+    Like(Operand, LikePattern),
+    IsNull(Operand),
+    IsMissing(Operand),
+    Bool(Operand),
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Path(JsonPath),
+    Literal(Jsonc),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum LikePattern {
+    /// A pattern whose only wildcard is a final `%`.
+    Prefix(String),
+    General(Vec<LikeToken>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LikeToken {
+    Char(char),
+    AnyChar,
+    AnyRun,
+}
+
+impl Filter {
+    pub fn parse(filter: &str) -> Result<Self> {
+        let mut parser = Parser::new(filter);
+        let expr = parse_or(&mut parser)?;
+        parser.skip_ws();
+        if parser.peek().is_some() {
+            return parser.error("unexpected character");
+        }
+        Ok(Self { expr })
+    }
+
+    pub fn eval(&self, json: JsoncSlice) -> Truth {
+        self.expr.eval(json)
+    }
+
+    /// Whether the filter is [`Truth::True`] for `json`, so that rows for which
+    /// it is unknown are left out as in a SQL `WHERE` clause.
+    pub fn matches(&self, json: JsoncSlice) -> bool {
+        self.eval(json) == Truth::True
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Error;
+
+    fn from_str(filter: &str) -> Result<Self> {
+        Self::parse(filter)
+    }
+}
+
+fn parse_or(parser: &mut Parser) -> Result<Expr> {
+    let mut expr = parse_and(parser)?;
+    loop {
+        parser.skip_ws();
+        if !parser.eat_str("||") {
+            return Ok(expr);
+        }
+        let rhs = parse_and(parser)?;
+        expr = Expr::Or(Box::new(expr), Box::new(rhs));
+    }
+}
+
+fn parse_and(parser: &mut Parser) -> Result<Expr> {
+    let mut expr = parse_unary(parser)?;
+    loop {
+        parser.skip_ws();
+        if !parser.eat_str("&&") {
+            return Ok(expr);
+        }
+        let rhs = parse_unary(parser)?;
+        expr = Expr::And(Box::new(expr), Box::new(rhs));
+    }
+}
+
+fn parse_unary(parser: &mut Parser) -> Result<Expr> {
+    parser.skip_ws();
+    if parser.eat('!') {
+        return Ok(Expr::Not(Box::new(parse_unary(parser)?)));
+    }
+    if parser.eat('(') {
+        let expr = parse_or(parser)?;
+        parser.skip_ws();
+        parser.expect(')')?;
+        return Ok(expr);
+    }
+    if eat_keyword(parser, "exists") {
+        parser.skip_ws();
+        parser.expect('(')?;
+        parser.skip_ws();
+        let path = JsonPath::parse_embedded(parser)?;
+        parser.skip_ws();
+        parser.expect(')')?;
+        return Ok(Expr::Not(Box::new(Expr::IsMissing(Operand::Path(path)))));
+    }
+    parse_predicate(parser)
+}
+
+fn parse_predicate(parser: &mut Parser) -> Result<Expr> {
+    let operand = parse_operand(parser)?;
+    parser.skip_ws();
+    let op = [
+        ("==", CompareOp::Eq),
+        ("!=", CompareOp::Ne),
+        ("<=", CompareOp::Le),
+        (">=", CompareOp::Ge),
+        ("<", CompareOp::Lt),
+        (">", CompareOp::Gt),
+    ]
+    .into_iter()
+    .find(|(token, _)| parser.eat_str(token));
+    if let Some((_, op)) = op {
+        parser.skip_ws();
+        return Ok(Expr::Compare(operand, op, parse_operand(parser)?));
+    }
+    if eat_keyword(parser, "in") {
+        parser.skip_ws();
+        parser.expect('(')?;
+        let mut list = Vec::new();
+        loop {
+            parser.skip_ws();
+            list.push(parse_literal(parser)?);
+            parser.skip_ws();
+            if parser.eat(')') {
+                return Ok(Expr::In(operand, list));
+            }
+            parser.expect(',')?;
+        }
+    }
+    if eat_keyword(parser, "like") {
+        parser.skip_ws();
+        if !matches!(parser.peek(), Some('"' | '\'')) {
+            return parser.error("expected a pattern string");
+        }
+        let pattern = parse_like_pattern(&parser.parse_string()?);
+        return Ok(Expr::Like(operand, pattern));
+    }
+    if eat_keyword(parser, "is") {
+        parser.skip_ws();
+        let negated = eat_keyword(parser, "not");
+        parser.skip_ws();
+        let expr = if eat_keyword(parser, "null") {
+            Expr::IsNull(operand)
+        } else if eat_keyword(parser, "missing") {
+            Expr::IsMissing(operand)
+        } else {
+            return parser.error("expected `null` or `missing`");
+        };
+        return Ok(if negated {
+            Expr::Not(Box::new(expr))
+        } else {
+            expr
+        });
+    }
+    Ok(Expr::Bool(operand))
+}
+
+fn parse_operand(parser: &mut Parser) -> Result<Operand> {
+    parser.skip_ws();
+    if parser.peek() == Some('$') {
+        return Ok(Operand::Path(JsonPath::parse_embedded(parser)?));
+    }
+    Ok(Operand::Literal(parse_literal(parser)?))
+}
+
+fn parse_literal(parser: &mut Parser) -> Result<Jsonc> {
+    let scalar = |node| Jsonc::new_with_values(vec![node], vec![], vec![]);
+    match parser.peek() {
+        Some('"' | '\'') => Ok(Jsonc::new_with_values(
+            vec![Node::String],
+            vec![parser.parse_string()?],
+            vec![],
+        )),
+        Some('-' | '0'..='9') => parser.parse_number(),
+        _ if eat_keyword(parser, "true") => Ok(scalar(Node::True)),
+        _ if eat_keyword(parser, "false") => Ok(scalar(Node::False)),
+        _ if eat_keyword(parser, "null") => Ok(scalar(Node::Null)),
+        _ => parser.error("expected a path or a literal"),
+    }
+}
+
+fn eat_keyword(parser: &mut Parser, keyword: &str) -> bool {
+    let rest = parser.rest();
+    let is_keyword = rest.starts_with(keyword)
+        && !rest[keyword.len()..].starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+    is_keyword && parser.eat_str(keyword)
+}
+
+fn parse_like_pattern(pattern: &str) -> LikePattern {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '%' => LikeToken::AnyRun,
+            '_' => LikeToken::AnyChar,
+            '\\' => LikeToken::Char(chars.next().unwrap_or('\\')),
+            _ => LikeToken::Char(c),
+        });
+    }
+    match tokens.split_last() {
+        Some((LikeToken::AnyRun, prefix))
+            if prefix
+                .iter()
+                .all(|token| matches!(token, LikeToken::Char(_))) =>
+        {
+            let prefix = prefix
+                .iter()
+                .map(|token| match token {
+                    LikeToken::Char(c) => *c,
+                    _ => unreachable!(),
+                })
+                .collect();
+            LikePattern::Prefix(prefix)
+        }
+        _ => LikePattern::General(tokens),
+    }
+}
+
+impl LikePattern {
+    fn matches(&self, s: &str) -> bool {
+        match self {
+            LikePattern::Prefix(prefix) => s.starts_with(prefix.as_str()),
+            LikePattern::General(tokens) => like_matches(tokens, &s.chars().collect::<Vec<_>>()),
+        }
+    }
+}
+
+/// Matches with backtracking to the most recent `%` only, which is enough
+/// because a later `%` can absorb anything an earlier one would.
+fn like_matches(tokens: &[LikeToken], chars: &[char]) -> bool {
+    let (mut t, mut c) = (0, 0);
+    let mut backtrack = None;
+    while c < chars.len() {
+        match tokens.get(t) {
+            Some(LikeToken::AnyRun) => {
+                backtrack = Some((t, c));
+                t += 1;
+            }
+            Some(LikeToken::AnyChar) => {
+                t += 1;
+                c += 1;
+            }
+            Some(LikeToken::Char(expected)) if *expected == chars[c] => {
+                t += 1;
+                c += 1;
+            }
+            _ => match backtrack {
+                Some((run, start)) => {
+                    backtrack = Some((run, start + 1));
+                    t = run + 1;
+                    c = start + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    tokens[t..].iter().all(|token| *token == LikeToken::AnyRun)
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, json: JsoncSlice<'a>) -> Option<JsoncSlice<'a>> {
+        match self {
+            Operand::Path(path) => json.get_json_path(path),
+            Operand::Literal(literal) => Some(literal.as_slice()),
+        }
+    }
+}
+
+impl Expr {
+    fn eval(&self, json: JsoncSlice) -> Truth {
+        match self {
+            Expr::Or(lhs, rhs) => match lhs.eval(json) {
+                Truth::True => Truth::True,
+                truth => truth.or(rhs.eval(json)),
+            },
+            Expr::And(lhs, rhs) => match lhs.eval(json) {
+                Truth::False => Truth::False,
+                truth => truth.and(rhs.eval(json)),
+            },
+            Expr::Not(expr) => expr.eval(json).not(),
+            Expr::Compare(lhs, op, rhs) => match (lhs.resolve(json), rhs.resolve(json)) {
+                (Some(lhs), Some(rhs)) => compare(lhs, *op, rhs),
+                _ => Truth::Unknown,
+            },
+            Expr::In(operand, list) => {
+                let Some(value) = operand.resolve(json).filter(|value| !value.is_null()) else {
+                    return Truth::Unknown;
+                };
+                let mut truth = Truth::False;
+                for item in list {
+                    truth = truth.or(compare(value, CompareOp::Eq, item.as_slice()));
+                }
+                truth
+            }
+            Expr::Like(operand, pattern) => match operand.resolve(json) {
+                Some(value) => match value.as_str() {
+                    Some(s) => pattern.matches(s).into(),
+                    None => Truth::Unknown,
+                },
+                None => Truth::Unknown,
+            },
+            Expr::IsNull(operand) => operand.resolve(json).is_some_and(|v| v.is_null()).into(),
+            Expr::IsMissing(operand) => operand.resolve(json).is_none().into(),
+            Expr::Bool(operand) => match operand.resolve(json).and_then(|v| v.as_bool()) {
+                Some(b) => b.into(),
+                None => Truth::Unknown,
+            },
+        }
+    }
+}
+
+fn compare(lhs: JsoncSlice, op: CompareOp, rhs: JsoncSlice) -> Truth {
+    let (kind, other_kind) = (lhs.kind(), rhs.kind());
+    if kind != other_kind || matches!(kind, None | Some(ValueKind::Null)) {
+        return Truth::Unknown;
+    }
+    let ordering = match kind {
+        Some(ValueKind::Number) => lhs.as_f64().unwrap().partial_cmp(&rhs.as_f64().unwrap()),
+        Some(ValueKind::String) => Some(lhs.as_str().cmp(&rhs.as_str())),
+        Some(ValueKind::Bool) => Some(lhs.as_bool().cmp(&rhs.as_bool())),
+        _ => {
+            return match op {
+                CompareOp::Eq => lhs.semantic_eq(&rhs).into(),
+                CompareOp::Ne => (!lhs.semantic_eq(&rhs)).into(),
+                _ => Truth::Unknown,
+            }
+        }
+    };
+    let Some(ordering) = ordering else {
+        return Truth::Unknown;
+    };
+    let result = match op {
+        CompareOp::Eq => ordering == Ordering::Equal,
+        CompareOp::Ne => ordering != Ordering::Equal,
+        CompareOp::Lt => ordering == Ordering::Less,
+        CompareOp::Le => ordering != Ordering::Greater,
+        CompareOp::Gt => ordering == Ordering::Greater,
+        CompareOp::Ge => ordering != Ordering::Less,
+    };
+    result.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_value;
+
+    fn eval(filter: &str, json: &Jsonc) -> Truth {
+        Filter::parse(filter).unwrap().eval(json.as_slice())
+    }
+
+    #[test]
+    fn test_filter_eval() {
+        let json = parse_value(
+            br#"{"n":150,"lang":"en","ok":true,"none":null,"tags":["a"],
+                "user":{"name":"Kim Lee","lang":"en"}}"#,
+        );
+        assert_eq!(eval("$.n > 100", &json), Truth::True);
+        assert_eq!(eval("$.n >= 150 && $.n <= 150.0", &json), Truth::True);
+        assert_eq!(eval("100 > $.n", &json), Truth::False);
+        assert_eq!(eval("$.lang == $.user.lang", &json), Truth::True);
+        assert_eq!(eval("$.lang < 'fr'", &json), Truth::True);
+        assert_eq!(eval("$.ok == true && $.ok", &json), Truth::True);
+        assert_eq!(eval("$.n == '150'", &json), Truth::Unknown);
+        assert_eq!(eval("$.missing > 1", &json), Truth::Unknown);
+        assert_eq!(eval("$.none == null", &json), Truth::Unknown);
+        assert_eq!(
+            eval("$.tags == $.tags && $.tags != 1", &json),
+            Truth::Unknown
+        );
+        assert_eq!(eval("$.tags == $.tags", &json), Truth::True);
+
+        assert_eq!(eval("$.missing > 1 || $.n > 1", &json), Truth::True);
+        assert_eq!(eval("$.missing > 1 || $.n < 1", &json), Truth::Unknown);
+        assert_eq!(eval("$.missing > 1 && $.n < 1", &json), Truth::False);
+        assert_eq!(eval("!($.missing > 1)", &json), Truth::Unknown);
+        assert_eq!(eval("!($.n > 1)", &json), Truth::False);
+
+        assert_eq!(eval("$.lang in ('ja', 'en')", &json), Truth::True);
+        assert_eq!(eval("$.lang in ('ja', 'fr')", &json), Truth::False);
+        assert_eq!(eval("$.lang in ('ja', null)", &json), Truth::Unknown);
+        assert_eq!(eval("$.none in ('ja')", &json), Truth::Unknown);
+        assert_eq!(eval("$.n in (1, 150)", &json), Truth::True);
+
+        assert_eq!(eval("$.user.name like 'Kim%'", &json), Truth::True);
+        assert_eq!(eval("$.user.name like '%Lee'", &json), Truth::True);
+        assert_eq!(eval("$.user.name like 'K_m %e'", &json), Truth::True);
+        assert_eq!(eval("$.user.name like 'Kim'", &json), Truth::False);
+        assert_eq!(eval(r"'50%' like '50\\%'", &json), Truth::True);
+        assert_eq!(eval("$.n like '1%'", &json), Truth::Unknown);
+
+        assert_eq!(
+            eval("$.none is null && $.none is not missing", &json),
+            Truth::True
+        );
+        assert_eq!(eval("$.missing is null", &json), Truth::False);
+        assert_eq!(
+            eval("$.missing is missing && $.n is not null", &json),
+            Truth::True
+        );
+        assert_eq!(
+            eval("exists($.tags[0]) && !exists($.tags[1])", &json),
+            Truth::True
+        );
+        assert_eq!(eval("exists($.none)", &json), Truth::True);
+    }
+
+    #[test]
+    fn test_filter_syntax() {
+        for filter in [
+            "$.a > 1 &&",
+            "$.a >",
+            "$.a in ()",
+            "$.a in ($.b)",
+            "$.a like 1",
+            "$.a is empty",
+            "exists(1)",
+            "($.a > 1",
+            "$.a > 1 )",
+            "$.a[x] > 1",
+        ] {
+            assert!(
+                matches!(Filter::parse(filter), Err(Error::Syntax { .. })),
+                "{}",
+                filter
+            );
+        }
+        let json = parse_value(br#"{"a b":{"c":1}}"#);
+        assert_eq!(eval(r#"$["a b"].c==1"#, &json), Truth::True);
+        assert!(Filter::parse("$.a==1&&($.b!=2||!$.c)").is_ok());
+    }
+}
@@ -0,0 +1,1137 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::jsonpath::Parser;
+use crate::slice::{JsoncSlice, ValueKind};
+use crate::value::{Jsonc, Node};
+
+/// A filter in a subset of the jq language, such as
+/// `[.statuses[] | select(.user.lang == "en") | {id, name: .user.name}]`.
+///
+/// Supported are `.`, `..`, `.a`, `."a"`, `.[e]`, `.[a:b]`, `.[]` and the
+/// `?` suffix; `|`, `,` and `//`; literals, `[...]` and `{...}`
+/// construction; `==`, `!=`, `<`, `<=`, `>`, `>=`, `and` and `or`; `+`, `-`,
+/// `*`, `/`, `%` and negation; and the builtins `select(f)`, `map(f)`,
+/// `keys`, `length`, `not` and `empty`.
+///
+/// An object that repeats a key is read as jq reads it: the key keeps its
+/// first position and takes its last value.
+///
+/// A `JqFilter` is `Send` and `Sync`, so one compiled filter can be shared
+/// by many worker threads.
+#[derive(Debug, Clone)]
+pub struct JqFilter {
+    expr: Expr,
+}
+
+const _: fn() = || {
+    fn assert<T: Send + Sync>() {}
+    assert::<JqFilter>();
+};
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Identity,
+    Recurse,
+    Index(Box<Expr>, Box<Expr>),
+    Slice(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>),
+    Iterate(Box<Expr>),
+    Try(Box<Expr>),
+    Literal(Arc<Jsonc>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(Expr, Expr)>),
+    Neg(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Alternative(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Select(Box<Expr>),
+    Map(Box<Expr>),
+    Keys,
+    Length,
+    Not,
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl JqFilter {
+    pub fn parse(filter: &str) -> Result<Self> {
+        let mut parser = Parser::new(filter);
+        parser.skip_ws();
+        if parser.peek().is_none() {
+            return Ok(Self {
+                expr: Expr::Identity,
+            });
+        }
+        let expr = parse_pipe(&mut parser, true)?;
+        parser.skip_ws();
+        if parser.peek().is_some() {
+            return parser.error("unexpected character");
+        }
+        Ok(Self { expr })
+    }
+
+    /// Runs the filter on `input`, returning every value it produces.
+    pub fn run(&self, input: JsoncSlice) -> Result<Vec<Jsonc>> {
+        let outputs = self.expr.eval(&Val::Slice(input))?;
+        Ok(outputs.into_iter().map(Val::into_jsonc).collect())
+    }
+}
+
+impl FromStr for JqFilter {
+    type Err = Error;
+
+    fn from_str(filter: &str) -> Result<Self> {
+        Self::parse(filter)
+    }
+}
+
+impl JsoncSlice<'_> {
+    /// Runs a jq filter on this value. See [`JqFilter`].
+    pub fn jq(&self, filter: &JqFilter) -> Result<Vec<Jsonc>> {
+        filter.run(*self)
+    }
+}
+
+fn parse_pipe(parser: &mut Parser, allow_comma: bool) -> Result<Expr> {
+    let lhs = if allow_comma {
+        parse_comma(parser)?
+    } else {
+        parse_alternative(parser)?
+    };
+    parser.skip_ws();
+    if parser.eat('|') {
+        let rhs = parse_pipe(parser, allow_comma)?;
+        return Ok(Expr::Pipe(Box::new(lhs), Box::new(rhs)));
+    }
+    Ok(lhs)
+}
+
+fn parse_comma(parser: &mut Parser) -> Result<Expr> {
+    let mut expr = parse_alternative(parser)?;
+    loop {
+        parser.skip_ws();
+        if !parser.eat(',') {
+            return Ok(expr);
+        }
+        let rhs = parse_alternative(parser)?;
+        expr = Expr::Comma(Box::new(expr), Box::new(rhs));
+    }
+}
+
+fn parse_alternative(parser: &mut Parser) -> Result<Expr> {
+    let lhs = parse_or(parser)?;
+    parser.skip_ws();
+    if parser.eat_str("//") {
+        let rhs = parse_alternative(parser)?;
+        return Ok(Expr::Alternative(Box::new(lhs), Box::new(rhs)));
+    }
+    Ok(lhs)
+}
+
+fn parse_or(parser: &mut Parser) -> Result<Expr> {
+    let mut expr = parse_and(parser)?;
+    loop {
+        parser.skip_ws();
+        if !eat_keyword(parser, "or") {
+            return Ok(expr);
+        }
+        let rhs = parse_and(parser)?;
+        expr = Expr::Or(Box::new(expr), Box::new(rhs));
+    }
+}
+
+fn parse_and(parser: &mut Parser) -> Result<Expr> {
+    let mut expr = parse_comparison(parser)?;
+    loop {
+        parser.skip_ws();
+        if !eat_keyword(parser, "and") {
+            return Ok(expr);
+        }
+        let rhs = parse_comparison(parser)?;
+        expr = Expr::And(Box::new(expr), Box::new(rhs));
+    }
+}
+
+fn parse_comparison(parser: &mut Parser) -> Result<Expr> {
+    let lhs = parse_additive(parser)?;
+    parser.skip_ws();
+    let op = [
+        ("==", BinaryOp::Eq),
+        ("!=", BinaryOp::Ne),
+        ("<=", BinaryOp::Le),
+        (">=", BinaryOp::Ge),
+        ("<", BinaryOp::Lt),
+        (">", BinaryOp::Gt),
+    ]
+    .into_iter()
+    .find(|(token, _)| parser.eat_str(token));
+    match op {
+        Some((_, op)) => {
+            let rhs = parse_additive(parser)?;
+            Ok(Expr::Binary(op, Box::new(lhs), Box::new(rhs)))
+        }
+        None => Ok(lhs),
+    }
+}
+
+fn parse_additive(parser: &mut Parser) -> Result<Expr> {
+    let mut expr = parse_multiplicative(parser)?;
+    loop {
+        parser.skip_ws();
+        let op = if parser.eat('+') {
+            BinaryOp::Add
+        } else if parser.eat('-') {
+            BinaryOp::Sub
+        } else {
+            return Ok(expr);
+        };
+        let rhs = parse_multiplicative(parser)?;
+        expr = Expr::Binary(op, Box::new(expr), Box::new(rhs));
+    }
+}
+
+fn parse_multiplicative(parser: &mut Parser) -> Result<Expr> {
+    let mut expr = parse_postfix(parser)?;
+    loop {
+        parser.skip_ws();
+        let op = if parser.eat('*') {
+            BinaryOp::Mul
+        } else if parser.rest().starts_with('/') && !parser.rest().starts_with("//") {
+            parser.bump();
+            BinaryOp::Div
+        } else if parser.eat('%') {
+            BinaryOp::Mod
+        } else {
+            return Ok(expr);
+        };
+        let rhs = parse_postfix(parser)?;
+        expr = Expr::Binary(op, Box::new(expr), Box::new(rhs));
+    }
+}
+
+fn parse_postfix(parser: &mut Parser) -> Result<Expr> {
+    let mut expr = parse_term(parser)?;
+    loop {
+        parser.skip_ws();
+        let rest = parser.rest();
+        if rest.starts_with(".[") {
+            parser.bump();
+        } else if rest.starts_with('.') && rest[1..].starts_with(|c| is_ident_start(c) || c == '"')
+        {
+            parser.bump();
+            let name = parse_field_name(parser)?;
+            expr = Expr::Index(Box::new(expr), Box::new(string_literal(name)));
+            continue;
+        }
+        if parser.eat('[') {
+            expr = parse_bracket_suffix(parser, expr)?;
+        } else if parser.eat('?') {
+            expr = Expr::Try(Box::new(expr));
+        } else {
+            return Ok(expr);
+        }
+    }
+}
+
+fn parse_bracket_suffix(parser: &mut Parser, target: Expr) -> Result<Expr> {
+    let target = Box::new(target);
+    parser.skip_ws();
+    if parser.eat(']') {
+        return Ok(Expr::Iterate(target));
+    }
+    let start = if parser.peek() == Some(':') {
+        None
+    } else {
+        Some(Box::new(parse_pipe(parser, true)?))
+    };
+    parser.skip_ws();
+    if !parser.eat(':') {
+        parser.expect(']')?;
+        return match start {
+            Some(index) => Ok(Expr::Index(target, index)),
+            None => parser.error("expected an index"),
+        };
+    }
+    parser.skip_ws();
+    let end = if parser.peek() == Some(']') {
+        None
+    } else {
+        Some(Box::new(parse_pipe(parser, true)?))
+    };
+    parser.skip_ws();
+    parser.expect(']')?;
+    if start.is_none() && end.is_none() {
+        return parser.error("expected an index");
+    }
+    Ok(Expr::Slice(target, start, end))
+}
+
+fn parse_term(parser: &mut Parser) -> Result<Expr> {
+    parser.skip_ws();
+    match parser.peek() {
+        Some('.') => {
+            parser.bump();
+            if parser.eat('.') {
+                Ok(Expr::Recurse)
+            } else if parser.peek().is_some_and(|c| is_ident_start(c) || c == '"') {
+                let name = parse_field_name(parser)?;
+                Ok(Expr::Index(
+                    Box::new(Expr::Identity),
+                    Box::new(string_literal(name)),
+                ))
+            } else {
+                Ok(Expr::Identity)
+            }
+        }
+        Some('0'..='9') => Ok(Expr::Literal(Arc::new(parser.parse_number()?))),
+        Some('"') => Ok(string_literal(parser.parse_string()?)),
+        Some('(') => {
+            parser.bump();
+            let expr = parse_pipe(parser, true)?;
+            parser.skip_ws();
+            parser.expect(')')?;
+            Ok(expr)
+        }
+        Some('[') => {
+            parser.bump();
+            parser.skip_ws();
+            if parser.eat(']') {
+                return Ok(Expr::Array(None));
+            }
+            let expr = parse_pipe(parser, true)?;
+            parser.skip_ws();
+            parser.expect(']')?;
+            Ok(Expr::Array(Some(Box::new(expr))))
+        }
+        Some('{') => parse_object(parser),
+        Some('-') => {
+            parser.bump();
+            Ok(Expr::Neg(Box::new(parse_postfix(parser)?)))
+        }
+        Some(c) if is_ident_start(c) => {
+            let name = parse_ident(parser);
+            let literal = |node| Ok(Expr::Literal(Arc::new(scalar(node))));
+            match name {
+                "true" => literal(Node::True),
+                "false" => literal(Node::False),
+                "null" => literal(Node::Null),
+                "keys" => Ok(Expr::Keys),
+                "length" => Ok(Expr::Length),
+                "not" => Ok(Expr::Not),
+                "empty" => Ok(Expr::Empty),
+                "select" => Ok(Expr::Select(Box::new(parse_argument(parser)?))),
+                "map" => Ok(Expr::Map(Box::new(parse_argument(parser)?))),
+                _ => parser.error("unknown function"),
+            }
+        }
+        _ => parser.error("expected a filter"),
+    }
+}
+
+fn parse_argument(parser: &mut Parser) -> Result<Expr> {
+    parser.skip_ws();
+    parser.expect('(')?;
+    let expr = parse_pipe(parser, true)?;
+    parser.skip_ws();
+    parser.expect(')')?;
+    Ok(expr)
+}
+
+fn parse_object(parser: &mut Parser) -> Result<Expr> {
+    parser.expect('{')?;
+    let mut entries = Vec::new();
+    parser.skip_ws();
+    if parser.eat('}') {
+        return Ok(Expr::Object(entries));
+    }
+    loop {
+        parser.skip_ws();
+        let (key, shorthand) = match parser.peek() {
+            Some('"') => {
+                let name = parser.parse_string()?;
+                (string_literal(name.clone()), Some(name))
+            }
+            Some('(') => {
+                parser.bump();
+                let key = parse_pipe(parser, true)?;
+                parser.skip_ws();
+                parser.expect(')')?;
+                (key, None)
+            }
+            Some(c) if is_ident_start(c) => {
+                let name = parse_ident(parser).to_string();
+                (string_literal(name.clone()), Some(name))
+            }
+            _ => return parser.error("expected an object key"),
+        };
+        parser.skip_ws();
+        let value = if parser.eat(':') {
+            parse_pipe(parser, false)?
+        } else if let Some(name) = shorthand {
+            Expr::Index(Box::new(Expr::Identity), Box::new(string_literal(name)))
+        } else {
+            return parser.error("expected `:`");
+        };
+        entries.push((key, value));
+        parser.skip_ws();
+        if parser.eat('}') {
+            return Ok(Expr::Object(entries));
+        }
+        parser.expect(',')?;
+    }
+}
+
+fn parse_field_name(parser: &mut Parser) -> Result<String> {
+    if parser.peek() == Some('"') {
+        parser.parse_string()
+    } else {
+        Ok(parse_ident(parser).to_string())
+    }
+}
+
+fn parse_ident<'s>(parser: &mut Parser<'s>) -> &'s str {
+    let rest = parser.rest();
+    let len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    parser.eat_str(&rest[..len]);
+    &rest[..len]
+}
+
+fn eat_keyword(parser: &mut Parser, keyword: &str) -> bool {
+    let rest = parser.rest();
+    let is_keyword = rest.starts_with(keyword)
+        && !rest[keyword.len()..].starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+    is_keyword && parser.eat_str(keyword)
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn string_literal(s: String) -> Expr {
+    Expr::Literal(Arc::new(Jsonc::new_with_values(
+        vec![Node::String],
+        vec![s],
+        vec![],
+    )))
+}
+
+fn scalar(node: Node) -> Jsonc {
+    Jsonc::new_with_values(vec![node], vec![], vec![])
+}
+
+/// A value produced while running a filter: either part of the input, or a
+/// value the filter built.
+#[derive(Debug, Clone)]
+enum Val<'a> {
+    Slice(JsoncSlice<'a>),
+    Owned(Arc<Jsonc>),
+}
+
+static NULL: [Node; 1] = [Node::Null];
+
+impl<'a> Val<'a> {
+    fn new(jsonc: Jsonc) -> Self {
+        Val::Owned(Arc::new(jsonc))
+    }
+
+    fn null() -> Self {
+        Val::Slice(null_slice())
+    }
+
+    fn number(n: f64) -> Self {
+        Val::new(Jsonc::new_with_values(vec![Node::Number], vec![], vec![n]))
+    }
+
+    fn string(s: String) -> Self {
+        Val::new(Jsonc::new_with_values(vec![Node::String], vec![s], vec![]))
+    }
+
+    fn bool(b: bool) -> Self {
+        Val::new(scalar(if b { Node::True } else { Node::False }))
+    }
+
+    fn slice(&self) -> JsoncSlice {
+        match self {
+            Val::Slice(slice) => *slice,
+            Val::Owned(jsonc) => jsonc.as_slice(),
+        }
+    }
+
+    fn into_jsonc(self) -> Jsonc {
+        match self {
+            Val::Slice(slice) => slice.into(),
+            Val::Owned(jsonc) => Arc::try_unwrap(jsonc).unwrap_or_else(|jsonc| (*jsonc).clone()),
+        }
+    }
+
+    /// The parts of this value chosen by `select`, borrowed from the input
+    /// when this value is, and copied out otherwise.
+    fn parts(
+        &self,
+        select: impl for<'b> FnOnce(JsoncSlice<'b>) -> Vec<JsoncSlice<'b>>,
+    ) -> Vec<Val<'a>> {
+        match self {
+            Val::Slice(slice) => select(*slice).into_iter().map(Val::Slice).collect(),
+            Val::Owned(jsonc) => select(jsonc.as_slice())
+                .into_iter()
+                .map(|part| Val::new(part.into()))
+                .collect(),
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        !matches!(self.slice().nodes.first(), Some(Node::Null | Node::False))
+    }
+}
+
+fn null_slice<'b>() -> JsoncSlice<'b> {
+    JsoncSlice {
+        nodes: &NULL,
+        strings: &[],
+        numbers: &[],
+    }
+}
+
+impl Expr {
+    fn eval<'a>(&self, input: &Val<'a>) -> Result<Vec<Val<'a>>> {
+        let mut outputs = Vec::new();
+        match self {
+            Expr::Identity => outputs.push(input.clone()),
+            Expr::Recurse => {
+                outputs = input.parts(|slice| {
+                    let mut descendants = Vec::new();
+                    push_descendants(slice, &mut descendants);
+                    descendants
+                })
+            }
+            Expr::Index(target, index) => {
+                let indexes = index.eval(input)?;
+                for target in target.eval(input)? {
+                    for index in &indexes {
+                        outputs.push(eval_index(&target, index)?);
+                    }
+                }
+            }
+            Expr::Slice(target, start, end) => {
+                let bound = |expr: &Option<Box<Expr>>| -> Result<Vec<_>> {
+                    match expr {
+                        Some(expr) => Ok(expr.eval(input)?.into_iter().map(Some).collect()),
+                        None => Ok(vec![None]),
+                    }
+                };
+                let (starts, ends) = (bound(start)?, bound(end)?);
+                for target in target.eval(input)? {
+                    for end in &ends {
+                        for start in &starts {
+                            outputs.push(eval_slice(&target, start.as_ref(), end.as_ref())?);
+                        }
+                    }
+                }
+            }
+            Expr::Iterate(target) => {
+                for target in target.eval(input)? {
+                    outputs.extend(iterate(&target)?);
+                }
+            }
+            Expr::Try(expr) => outputs = expr.eval(input).unwrap_or_default(),
+            Expr::Literal(literal) => outputs.push(Val::Owned(literal.clone())),
+            Expr::Array(None) => outputs.push(build_array(&[])),
+            Expr::Array(Some(expr)) => outputs.push(build_array(&expr.eval(input)?)),
+            Expr::Object(entries) => {
+                let mut objects = vec![Vec::new()];
+                for (key, value) in entries {
+                    let keys = key
+                        .eval(input)?
+                        .into_iter()
+                        .map(|key| match key.slice().as_str() {
+                            Some(key) => Ok(key.to_string()),
+                            None => Err(eval_error(format!(
+                                "object keys must be strings, not {}",
+                                type_name(&key)
+                            ))),
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    let values = value.eval(input)?;
+                    let mut extended = Vec::new();
+                    for object in &objects {
+                        for key in &keys {
+                            for value in &values {
+                                let mut object: Vec<_> = object.clone();
+                                object.push((key.clone(), value.clone()));
+                                extended.push(object);
+                            }
+                        }
+                    }
+                    objects = extended;
+                }
+                outputs = objects.into_iter().map(build_object).collect();
+            }
+            Expr::Neg(expr) => {
+                for value in expr.eval(input)? {
+                    match value.slice().as_f64() {
+                        Some(n) => outputs.push(Val::number(-n)),
+                        None => {
+                            return Err(eval_error(format!(
+                                "{} cannot be negated",
+                                type_name(&value)
+                            )))
+                        }
+                    }
+                }
+            }
+            Expr::Pipe(lhs, rhs) => {
+                for value in lhs.eval(input)? {
+                    outputs.extend(rhs.eval(&value)?);
+                }
+            }
+            Expr::Comma(lhs, rhs) => {
+                outputs = lhs.eval(input)?;
+                outputs.extend(rhs.eval(input)?);
+            }
+            Expr::Alternative(lhs, rhs) => {
+                outputs = lhs.eval(input).unwrap_or_default();
+                outputs.retain(Val::is_truthy);
+                if outputs.is_empty() {
+                    outputs = rhs.eval(input)?;
+                }
+            }
+            Expr::Or(lhs, rhs) => {
+                for value in lhs.eval(input)? {
+                    if value.is_truthy() {
+                        outputs.push(Val::bool(true));
+                    } else {
+                        outputs.extend(rhs.eval(input)?.iter().map(|v| Val::bool(v.is_truthy())));
+                    }
+                }
+            }
+            Expr::And(lhs, rhs) => {
+                for value in lhs.eval(input)? {
+                    if value.is_truthy() {
+                        outputs.extend(rhs.eval(input)?.iter().map(|v| Val::bool(v.is_truthy())));
+                    } else {
+                        outputs.push(Val::bool(false));
+                    }
+                }
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(input)?;
+                for rhs in rhs.eval(input)? {
+                    for lhs in &lhs {
+                        outputs.push(eval_binary(*op, lhs, &rhs)?);
+                    }
+                }
+            }
+            Expr::Select(cond) => {
+                for value in cond.eval(input)? {
+                    if value.is_truthy() {
+                        outputs.push(input.clone());
+                    }
+                }
+            }
+            Expr::Map(expr) => {
+                let mut mapped = Vec::new();
+                for value in iterate(input)? {
+                    mapped.extend(expr.eval(&value)?);
+                }
+                outputs.push(build_array(&mapped));
+            }
+            Expr::Keys => outputs.push(keys(input)?),
+            Expr::Length => outputs.push(length(input)?),
+            Expr::Not => outputs.push(Val::bool(!input.is_truthy())),
+            Expr::Empty => {}
+        }
+        Ok(outputs)
+    }
+}
+
+fn eval_error(msg: String) -> Error {
+    Error::Eval(msg)
+}
+
+fn type_name(value: &Val) -> &'static str {
+    match value.slice().kind() {
+        None | Some(ValueKind::Null) => "null",
+        Some(ValueKind::Bool) => "boolean",
+        Some(ValueKind::Number) => "number",
+        Some(ValueKind::String) => "string",
+        Some(ValueKind::Array) => "array",
+        Some(ValueKind::Object) => "object",
+    }
+}
+
+fn push_descendants<'b>(json: JsoncSlice<'b>, descendants: &mut Vec<JsoncSlice<'b>>) {
+    descendants.push(json);
+    if let Some(elements) = json.array_iter() {
+        for element in elements {
+            push_descendants(element, descendants);
+        }
+    } else if json.kind() == Some(ValueKind::Object) {
+        for (_, value) in entries(json) {
+            push_descendants(value, descendants);
+        }
+    }
+}
+
+fn eval_index<'a>(target: &Val<'a>, index: &Val) -> Result<Val<'a>> {
+    let (target_kind, index_slice) = (target.slice().kind(), index.slice());
+    match (target_kind, index_slice.kind()) {
+        (Some(ValueKind::Null), Some(ValueKind::String | ValueKind::Number)) => Ok(Val::null()),
+        (Some(ValueKind::Object), Some(ValueKind::String)) => {
+            let key = index_slice.as_str().unwrap();
+            let found = target.parts(|object| {
+                let found = entries(object).into_iter().find(|(k, _)| *k == key);
+                vec![found.map_or_else(null_slice, |(_, value)| value)]
+            });
+            Ok(found.into_iter().next().unwrap())
+        }
+        (Some(ValueKind::Array), Some(ValueKind::Number)) => {
+            let len = target.slice().len().unwrap() as f64;
+            let mut idx = index_slice.as_f64().unwrap().floor();
+            if idx < 0.0 {
+                idx += len;
+            }
+            if !(0.0..len).contains(&idx) {
+                return Ok(Val::null());
+            }
+            let found = target.parts(|array| vec![array.get_by_idx(idx as usize).unwrap()]);
+            Ok(found.into_iter().next().unwrap())
+        }
+        _ => Err(eval_error(format!(
+            "cannot index {} with {}",
+            type_name(target),
+            type_name(index)
+        ))),
+    }
+}
+
+fn eval_slice<'a>(target: &Val<'a>, start: Option<&Val>, end: Option<&Val>) -> Result<Val<'a>> {
+    let len = match target.slice().kind() {
+        Some(ValueKind::Null) => return Ok(Val::null()),
+        Some(ValueKind::Array) => target.slice().len().unwrap(),
+        Some(ValueKind::String) => target.slice().as_str().unwrap().chars().count(),
+        _ => return Err(eval_error(format!("cannot slice {}", type_name(target)))),
+    };
+    let bound = |value: Option<&Val>, default: usize, round: fn(f64) -> f64| {
+        let Some(value) = value.filter(|value| !value.slice().is_null()) else {
+            return Ok(default);
+        };
+        let Some(n) = value.slice().as_f64() else {
+            return Err(eval_error(format!(
+                "cannot slice with {}",
+                type_name(value)
+            )));
+        };
+        let n = round(n);
+        let n = if n < 0.0 { n + len as f64 } else { n };
+        Ok(n.clamp(0.0, len as f64) as usize)
+    };
+    let start = bound(start, 0, f64::floor)?;
+    let end = bound(end, len, f64::ceil)?.max(start);
+    match target.slice().as_str() {
+        Some(s) => Ok(Val::string(
+            s.chars().skip(start).take(end - start).collect(),
+        )),
+        None => {
+            let elements = iterate(target)?;
+            Ok(build_array(&elements[start..end]))
+        }
+    }
+}
+
+fn iterate<'a>(value: &Val<'a>) -> Result<Vec<Val<'a>>> {
+    match value.slice().kind() {
+        Some(ValueKind::Array) => Ok(value.parts(|array| array.array_iter().unwrap().collect())),
+        Some(ValueKind::Object) => Ok(value.parts(|object| {
+            entries(object)
+                .into_iter()
+                .map(|(_, value)| value)
+                .collect()
+        })),
+        _ => Err(eval_error(format!(
+            "cannot iterate over {}",
+            type_name(value)
+        ))),
+    }
+}
+
+fn keys<'a>(value: &Val) -> Result<Val<'a>> {
+    let slice = value.slice();
+    match slice.kind() {
+        Some(ValueKind::Object) => {
+            let mut keys: Vec<_> = entries(slice).into_iter().map(|(key, _)| key).collect();
+            keys.sort_unstable();
+            let keys: Vec<_> = keys
+                .into_iter()
+                .map(|key| Val::string(key.to_string()))
+                .collect();
+            Ok(build_array(&keys))
+        }
+        Some(ValueKind::Array) => {
+            let indexes: Vec<_> = (0..slice.len().unwrap())
+                .map(|idx| Val::number(idx as f64))
+                .collect();
+            Ok(build_array(&indexes))
+        }
+        _ => Err(eval_error(format!("{} has no keys", type_name(value)))),
+    }
+}
+
+fn length<'a>(value: &Val) -> Result<Val<'a>> {
+    let slice = value.slice();
+    let len = match slice.kind() {
+        None | Some(ValueKind::Null) => 0.0,
+        Some(ValueKind::Number) => slice.as_f64().unwrap().abs(),
+        Some(ValueKind::String) => slice.as_str().unwrap().chars().count() as f64,
+        Some(ValueKind::Array) => slice.len().unwrap() as f64,
+        Some(ValueKind::Object) => entries(slice).len() as f64,
+        Some(ValueKind::Bool) => return Err(eval_error("boolean has no length".to_string())),
+    };
+    Ok(Val::number(len))
+}
+
+fn eval_binary<'a>(op: BinaryOp, lhs: &Val<'a>, rhs: &Val<'a>) -> Result<Val<'a>> {
+    let (a, b) = (lhs.slice(), rhs.slice());
+    let numbers = a.as_f64().zip(b.as_f64());
+    let value = match op {
+        BinaryOp::Eq => Val::bool(a.semantic_eq(&b)),
+        BinaryOp::Ne => Val::bool(!a.semantic_eq(&b)),
+        BinaryOp::Lt => Val::bool(jq_cmp(a, b) == Ordering::Less),
+        BinaryOp::Le => Val::bool(jq_cmp(a, b) != Ordering::Greater),
+        BinaryOp::Gt => Val::bool(jq_cmp(a, b) == Ordering::Greater),
+        BinaryOp::Ge => Val::bool(jq_cmp(a, b) != Ordering::Less),
+        BinaryOp::Add => match (a.kind(), b.kind()) {
+            (Some(ValueKind::Null), _) => rhs.clone(),
+            (_, Some(ValueKind::Null)) => lhs.clone(),
+            (Some(ValueKind::Number), Some(ValueKind::Number)) => {
+                let (a, b) = numbers.unwrap();
+                Val::number(a + b)
+            }
+            (Some(ValueKind::String), Some(ValueKind::String)) => {
+                Val::string([a.as_str().unwrap(), b.as_str().unwrap()].concat())
+            }
+            (Some(ValueKind::Array), Some(ValueKind::Array)) => {
+                let mut elements = iterate(lhs)?;
+                elements.extend(iterate(rhs)?);
+                build_array(&elements)
+            }
+            (Some(ValueKind::Object), Some(ValueKind::Object)) => {
+                let entries = a.object_iter().unwrap().chain(b.object_iter().unwrap());
+                build_object(
+                    entries
+                        .map(|(key, value)| (key.to_string(), Val::new(value.into())))
+                        .collect(),
+                )
+            }
+            _ => return Err(binary_error(lhs, rhs, "added")),
+        },
+        BinaryOp::Sub => match (a.kind(), b.kind()) {
+            (Some(ValueKind::Number), Some(ValueKind::Number)) => {
+                let (a, b) = numbers.unwrap();
+                Val::number(a - b)
+            }
+            (Some(ValueKind::Array), Some(ValueKind::Array)) => {
+                let removed = iterate(rhs)?;
+                let mut elements = iterate(lhs)?;
+                elements.retain(|element| {
+                    !removed
+                        .iter()
+                        .any(|removed| element.slice().semantic_eq(&removed.slice()))
+                });
+                build_array(&elements)
+            }
+            _ => return Err(binary_error(lhs, rhs, "subtracted")),
+        },
+        BinaryOp::Mul => match numbers {
+            Some((a, b)) => Val::number(a * b),
+            None => return Err(binary_error(lhs, rhs, "multiplied")),
+        },
+        BinaryOp::Div => match numbers {
+            Some((_, b)) if b == 0.0 => return Err(binary_error(lhs, rhs, "divided by zero")),
+            Some((a, b)) => Val::number(a / b),
+            None => return Err(binary_error(lhs, rhs, "divided")),
+        },
+        BinaryOp::Mod => match numbers.map(|(a, b)| (a as i64, b as i64)) {
+            Some((_, 0)) => return Err(binary_error(lhs, rhs, "divided by zero")),
+            Some((a, b)) => Val::number(a.wrapping_rem(b) as f64),
+            None => return Err(binary_error(lhs, rhs, "divided")),
+        },
+    };
+    Ok(value)
+}
+
+fn binary_error(lhs: &Val, rhs: &Val, verb: &str) -> Error {
+    eval_error(format!(
+        "{} and {} cannot be {}",
+        type_name(lhs),
+        type_name(rhs),
+        verb
+    ))
+}
+
+/// Orders values the way jq's `<` and `sort` do: null < false < true <
+/// numbers < strings < arrays < objects, with arrays compared element by
+/// element and objects by their sorted keys and then their values.
+fn jq_cmp(a: JsoncSlice, b: JsoncSlice) -> Ordering {
+    let rank = |json: &JsoncSlice| match json.nodes.first() {
+        None | Some(Node::Null) => 0,
+        Some(Node::False) => 1,
+        Some(Node::True) => 2,
+        Some(Node::Number) => 3,
+        Some(Node::String) => 4,
+        Some(Node::StartArray) => 5,
+        _ => 6,
+    };
+    let ordering = rank(&a).cmp(&rank(&b));
+    if ordering != Ordering::Equal {
+        return ordering;
+    }
+    match a.kind() {
+        Some(ValueKind::Number) => {
+            let (a, b) = (a.as_f64().unwrap(), b.as_f64().unwrap());
+            a.partial_cmp(&b)
+                .unwrap_or_else(|| b.is_nan().cmp(&a.is_nan()))
+        }
+        Some(ValueKind::String) => a.as_str().cmp(&b.as_str()),
+        Some(ValueKind::Array) => {
+            let mut b_elements = b.array_iter().unwrap();
+            for a_element in a.array_iter().unwrap() {
+                let Some(b_element) = b_elements.next() else {
+                    return Ordering::Greater;
+                };
+                let ordering = jq_cmp(a_element, b_element);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            if b_elements.next().is_some() {
+                Ordering::Less
+            } else {
+                Ordering::Equal
+            }
+        }
+        Some(ValueKind::Object) => {
+            let (a, b) = (sorted_entries(a), sorted_entries(b));
+            let ordering = a
+                .iter()
+                .map(|(key, _)| key)
+                .cmp(b.iter().map(|(key, _)| key));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            for ((_, a_value), (_, b_value)) in a.into_iter().zip(b) {
+                let ordering = jq_cmp(a_value, b_value);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        }
+        _ => Ordering::Equal,
+    }
+}
+
+/// The entries of an object as jq reads them: a repeated key keeps its first
+/// position and takes its last value, as in [`build_object`].
+fn entries(object: JsoncSlice) -> Vec<(&str, JsoncSlice)> {
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    let mut merged: Vec<(&str, JsoncSlice)> = Vec::new();
+    for (key, value) in object.object_iter().unwrap() {
+        match positions.get(key) {
+            Some(&position) => merged[position].1 = value,
+            None => {
+                positions.insert(key, merged.len());
+                merged.push((key, value));
+            }
+        }
+    }
+    merged
+}
+
+/// The [`entries`] of an object sorted by key.
+fn sorted_entries(object: JsoncSlice) -> Vec<(&str, JsoncSlice)> {
+    let mut entries = entries(object);
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries
+}
+
+fn build_array<'a>(elements: &[Val]) -> Val<'a> {
+    let mut jsonc = Jsonc::new();
+    jsonc.nodes.push(Node::StartArray);
+    for element in elements {
+        push_value(&mut jsonc, element.slice());
+    }
+    jsonc.nodes.push(Node::EndArray);
+    Val::new(jsonc)
+}
+
+/// Builds an object from `entries` in order. A repeated key keeps its first
+/// position and takes its last value.
+fn build_object<'a>(entries: Vec<(String, Val)>) -> Val<'a> {
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut merged: Vec<(String, Val)> = Vec::with_capacity(entries.len());
+    for (key, value) in entries {
+        match positions.get(&key) {
+            Some(&position) => merged[position].1 = value,
+            None => {
+                positions.insert(key.clone(), merged.len());
+                merged.push((key, value));
+            }
+        }
+    }
+    let mut jsonc = Jsonc::new();
+    jsonc.nodes.push(Node::StartObject);
+    for (key, value) in merged {
+        jsonc.nodes.push(Node::Key);
+        jsonc.strings.push(key);
+        push_value(&mut jsonc, value.slice());
+    }
+    jsonc.nodes.push(Node::EndObject);
+    Val::new(jsonc)
+}
+
+fn push_value(jsonc: &mut Jsonc, value: JsoncSlice) {
+    jsonc.nodes.extend_from_slice(value.nodes);
+    jsonc.strings.extend_from_slice(value.strings);
+    jsonc.numbers.extend_from_slice(value.numbers);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode;
+    use crate::parser::parse_value;
+
+    fn run(input: &str, filter: &str) -> Vec<String> {
+        let input = parse_value(input.as_bytes());
+        let filter = JqFilter::parse(filter).unwrap();
+        filter
+            .run(input.as_slice())
+            .unwrap()
+            .iter()
+            .map(decode)
+            .collect()
+    }
+
+    #[test]
+    fn test_jq_paths() {
+        let input = r#"{"a":{"b":[1,2,{"c":"x"}]},"weird key":true}"#;
+        assert_eq!(
+            run(input, "."),
+            [r#"{"a":{"b":[1,2,{"c":"x"}]},"weird key":true}"#]
+        );
+        assert_eq!(run(input, ".a.b[2].c"), [r#""x""#]);
+        assert_eq!(
+            run(input, r#"."weird key", .["weird key"]"#),
+            ["true", "true"]
+        );
+        assert_eq!(run(input, ".a.b[-1]"), [r#"{"c":"x"}"#]);
+        assert_eq!(run(input, ".a.b[5], .missing.x"), ["null", "null"]);
+        assert_eq!(run(input, ".a.b[]"), ["1", "2", r#"{"c":"x"}"#]);
+        assert_eq!(run(input, ".a.b[1:]"), [r#"[2,{"c":"x"}]"#]);
+        assert_eq!(run(input, ".a.b[:-1]"), ["[1,2]"]);
+        assert_eq!(run(input, r#""abcdef" | .[2:4]"#), [r#""cd""#]);
+        assert_eq!(run(input, "[..] | length"), ["8"]);
+        assert_eq!(run(input, ".a.b[2].c.d?"), Vec::<String>::new());
+        assert_eq!(run(input, ".a.b[0].c? // \"none\""), [r#""none""#]);
+
+        let input = r#"{"a":1,"b":2,"a":3}"#;
+        assert_eq!(run(input, "length, keys"), ["2", r#"["a","b"]"#]);
+        assert_eq!(run(input, ".a, .[]"), ["3", "3", "2"]);
+        assert_eq!(run(input, "[..] | length"), ["3"]);
+        assert_eq!(run(input, r#". == {"b":2,"a":3}"#), ["true"]);
+    }
+
+    #[test]
+    fn test_jq_construction() {
+        let input = r#"{"user":{"id":7,"name":"kim"},"tags":["a","b"]}"#;
+        assert_eq!(
+            run(input, "{id: .user.id, name: .user.name | length, tags}"),
+            [r#"{"id":7,"name":3,"tags":["a","b"]}"#]
+        );
+        assert_eq!(
+            run(input, "{(.tags[]): .user.id}"),
+            [r#"{"a":7}"#, r#"{"b":7}"#]
+        );
+        assert_eq!(run(input, "[.tags[], .user.id]"), [r#"["a","b",7]"#]);
+        assert_eq!(run(input, ".user | keys"), [r#"["id","name"]"#]);
+        assert_eq!(run(input, ".tags | keys"), ["[0,1]"]);
+        assert_eq!(run(input, "{a: 1} + {b: 2} + {a: 3}"), [r#"{"a":3,"b":2}"#]);
+        assert_eq!(run(input, ".tags + [\"c\"] - [\"a\"]"), [r#"["b","c"]"#]);
+        assert_eq!(run(input, "[]"), ["[]"]);
+        assert_eq!(run(input, "{}"), ["{}"]);
+    }
+
+    #[test]
+    fn test_jq_operators() {
+        let input = r#"[{"n":1,"ok":true},{"n":5,"ok":false},{"n":3,"ok":null}]"#;
+        assert_eq!(run(input, "map(.n * 2 + 1)"), ["[3,11,7]"]);
+        assert_eq!(run(input, ".[] | select(.n > 2) | .n"), ["5", "3"]);
+        assert_eq!(run(input, "map(select(.ok | not)) | length"), ["2"]);
+        assert_eq!(
+            run(input, "map(.n % 2 == 1 and .n != 5)"),
+            ["[true,false,true]"]
+        );
+        assert_eq!(run(input, ".[0].n - -.[1].n / 5"), ["2"]);
+        assert_eq!(run(input, "(1, 2) + (10, 20)"), ["11", "12", "21", "22"]);
+        assert_eq!(run(input, "[1, 2] | .[] | empty"), Vec::<String>::new());
+        assert_eq!(
+            run(
+                input,
+                "null < false, false < true, 1 < \"a\", [1] < [1, 0], {} < []"
+            ),
+            ["true", "true", "true", "true", "false"]
+        );
+        assert_eq!(
+            run(input, "\"a\" + \"b\", null + 1, (-3 | length)"),
+            [r#""ab""#, "1", "3"]
+        );
+    }
+
+    #[test]
+    fn test_jq_errors() {
+        let input = parse_value(br#"{"a":[1,"x"],"b":true}"#);
+        for filter in [".a |", ".[", "{a", "foo", ".a ]", "map(.)x", "{1: 2}"] {
+            assert!(
+                matches!(JqFilter::parse(filter), Err(Error::Syntax { .. })),
+                "{}",
+                filter
+            );
+        }
+        for filter in [
+            ".a.b",
+            ".b[]",
+            ".a[0] + .a[1]",
+            ".b | length",
+            ".a | keys | .[0] / 0",
+        ] {
+            let filter = JqFilter::parse(filter).unwrap();
+            assert!(
+                matches!(filter.run(input.as_slice()), Err(Error::Eval(_))),
+                "{:?}",
+                filter
+            );
+        }
+    }
+}
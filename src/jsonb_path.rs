@@ -0,0 +1,430 @@
+//! Evaluation of the JSONPath ASTs that `jsonb::jsonpath` parses, giving the
+//! same results as the `jsonb` crate does for the equivalent binary value.
+//!
+//! `jsonb` stores objects with their keys sorted and a repeated key holding
+//! its last value, so here `.*` visits entries in key order and a field name
+//! selects the last entry with that key, unlike [`JsoncSlice::get_by_path`].
+//! Paths are evaluated in lax mode: `[*]` on a value that is not an array
+//! selects the value itself, and a step that does not apply, such as a field
+//! of an array or an index out of range, selects nothing.
+//!
+//! In a filter `? (...)` the value itself is tested. A comparison holds if it
+//! holds for any pair of scalars that its operands select, and scalars of
+//! different types compare as `null < booleans < numbers < strings`.
+
+use std::cmp::Ordering;
+
+use jsonb::jsonpath::{
+    ArrayIndex, BinaryOperator, Expr, FilterFunc, Index, JsonPath, Path, PathValue,
+};
+use jsonb::Number;
+
+use crate::error::{Error, Result};
+use crate::slice::{JsoncSlice, ValueKind};
+use crate::value::{Jsonc, Node};
+
+impl<'a> JsoncSlice<'a> {
+    /// Selects the values that `path` matches, in the order `jsonb` returns
+    /// them. A predicate path such as `$.a > 1` selects its result, `true`
+    /// or `false`, as `jsonb` does.
+    pub fn jsonb_path_query(&self, path: &JsonPath) -> Vec<JsoncSlice<'a>> {
+        match path.paths.as_slice() {
+            [Path::Predicate(expr)] => vec![boolean(eval_filter(*self, *self, expr))],
+            paths => select(*self, *self, paths),
+        }
+    }
+
+    /// The first value that `path` matches.
+    pub fn jsonb_path_query_first(&self, path: &JsonPath) -> Option<JsoncSlice<'a>> {
+        self.jsonb_path_query(path).into_iter().next()
+    }
+
+    /// Tests whether `path` matches any value. Like `jsonb`, this holds for
+    /// every predicate path, whatever it evaluates to.
+    pub fn jsonb_path_exists(&self, path: &JsonPath) -> bool {
+        !self.jsonb_path_query(path).is_empty()
+    }
+
+    /// Evaluates a predicate path such as `$.a > 1`, failing if `path` is
+    /// not a predicate.
+    pub fn jsonb_path_match(&self, path: &JsonPath) -> Result<bool> {
+        match path.paths.as_slice() {
+            [Path::Predicate(expr)] => Ok(eval_filter(*self, *self, expr)),
+            _ => Err(Error::Eval("the path is not a predicate".to_string())),
+        }
+    }
+}
+
+impl Jsonc {
+    /// See [`JsoncSlice::jsonb_path_query`].
+    pub fn jsonb_path_query(&self, path: &JsonPath) -> Vec<JsoncSlice> {
+        self.as_slice().jsonb_path_query(path)
+    }
+
+    /// See [`JsoncSlice::jsonb_path_query_first`].
+    pub fn jsonb_path_query_first(&self, path: &JsonPath) -> Option<JsoncSlice> {
+        self.as_slice().jsonb_path_query_first(path)
+    }
+
+    /// See [`JsoncSlice::jsonb_path_exists`].
+    pub fn jsonb_path_exists(&self, path: &JsonPath) -> bool {
+        self.as_slice().jsonb_path_exists(path)
+    }
+
+    /// See [`JsoncSlice::jsonb_path_match`].
+    pub fn jsonb_path_match(&self, path: &JsonPath) -> Result<bool> {
+        self.as_slice().jsonb_path_match(path)
+    }
+}
+
+/// The result of a predicate path, which borrows nothing from the value.
+fn boolean<'a>(value: bool) -> JsoncSlice<'a> {
+    JsoncSlice {
+        nodes: if value { &[Node::True] } else { &[Node::False] },
+        strings: &[],
+        numbers: &[],
+    }
+}
+
+/// Applies each step of `paths` to every value the previous step selected,
+/// starting from `current`.
+fn select<'a>(
+    root: JsoncSlice<'a>,
+    current: JsoncSlice<'a>,
+    paths: &[Path],
+) -> Vec<JsoncSlice<'a>> {
+    let mut values = vec![current];
+    for path in paths {
+        let mut next = Vec::new();
+        for value in values {
+            select_step(root, value, path, &mut next);
+        }
+        values = next;
+    }
+    values
+}
+
+fn select_step<'a>(
+    root: JsoncSlice<'a>,
+    value: JsoncSlice<'a>,
+    path: &Path,
+    out: &mut Vec<JsoncSlice<'a>>,
+) {
+    match path {
+        Path::Root => out.push(root),
+        Path::Current => out.push(value),
+        Path::DotWildcard => {
+            if value.kind() == Some(ValueKind::Object) {
+                out.extend(jsonb_entries(value).into_iter().map(|(_, v)| v));
+            }
+        }
+        Path::BracketWildcard => match value.array_iter() {
+            Some(elements) => out.extend(elements),
+            None => out.push(value),
+        },
+        Path::ColonField(name) | Path::DotField(name) | Path::ObjectField(name) => {
+            if let Some(mut entries) = value.object_iter() {
+                out.extend(entries.rfind(|(key, _)| key == name).map(|(_, v)| v));
+            }
+        }
+        Path::ArrayIndices(indices) => {
+            if let Some(elements) = value.array_iter() {
+                let elements: Vec<_> = elements.collect();
+                for index in indices {
+                    let len = elements.len() as i64;
+                    let (start, end) = match index {
+                        ArrayIndex::Index(index) => {
+                            let idx = resolve_index(index, len);
+                            (idx, idx)
+                        }
+                        ArrayIndex::Slice((start, end)) => (
+                            resolve_index(start, len).max(0),
+                            resolve_index(end, len).min(len - 1),
+                        ),
+                    };
+                    if start >= 0 && end < len {
+                        out.extend((start..=end).map(|i| elements[i as usize]));
+                    }
+                }
+            }
+        }
+        Path::FilterExpr(expr) => {
+            if eval_filter(root, value, expr) {
+                out.push(value);
+            }
+        }
+        Path::Predicate(_) => {}
+    }
+}
+
+/// The entries of an object as `jsonb` stores them: sorted by key, keeping
+/// the last value of a repeated key.
+fn jsonb_entries(object: JsoncSlice) -> Vec<(&str, JsoncSlice)> {
+    // Reversed so that the stable sort puts the last entry of a repeated key
+    // first, where `dedup` keeps it.
+    let mut entries: Vec<_> = object.object_iter().unwrap().rev().collect();
+    entries.sort_by_key(|(key, _)| *key);
+    entries.dedup_by_key(|(key, _)| *key);
+    entries
+}
+
+/// `last` is `len - 1`, and `last - n` is written `LastIndex(-n)`.
+fn resolve_index(index: &Index, len: i64) -> i64 {
+    match index {
+        Index::Index(idx) => *idx as i64,
+        Index::LastIndex(idx) => len - 1 + *idx as i64,
+    }
+}
+
+fn eval_filter(root: JsoncSlice, current: JsoncSlice, expr: &Expr) -> bool {
+    match expr {
+        Expr::BinaryOp {
+            op: BinaryOperator::And,
+            left,
+            right,
+        } => eval_filter(root, current, left) && eval_filter(root, current, right),
+        Expr::BinaryOp {
+            op: BinaryOperator::Or,
+            left,
+            right,
+        } => eval_filter(root, current, left) || eval_filter(root, current, right),
+        Expr::BinaryOp { op, left, right } => {
+            let lhs = operand(root, current, left);
+            let rhs = operand(root, current, right);
+            lhs.iter().any(|l| rhs.iter().any(|r| compare(l, op, r)))
+        }
+        Expr::FilterFunc(FilterFunc::Exists(paths)) => !select(root, current, paths).is_empty(),
+        Expr::Paths(_) | Expr::Value(_) => false,
+    }
+}
+
+/// A scalar operand of a comparison. The variants are in `jsonb`'s order
+/// for values of different types.
+#[derive(Debug, PartialEq, PartialOrd)]
+enum Scalar<'a> {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(&'a str),
+}
+
+/// The scalars an operand selects; arrays and objects are skipped.
+fn operand<'a>(root: JsoncSlice<'a>, current: JsoncSlice<'a>, expr: &'a Expr) -> Vec<Scalar<'a>> {
+    match expr {
+        Expr::Value(value) => vec![match value.as_ref() {
+            PathValue::Null => Scalar::Null,
+            PathValue::Boolean(b) => Scalar::Boolean(*b),
+            PathValue::Number(n) => Scalar::Number(number_to_f64(n)),
+            PathValue::String(s) => Scalar::String(s),
+        }],
+        Expr::Paths(paths) => select(root, current, paths)
+            .into_iter()
+            .filter_map(|value| match value.kind()? {
+                ValueKind::Null => Some(Scalar::Null),
+                ValueKind::Bool => value.as_bool().map(Scalar::Boolean),
+                ValueKind::Number => value.as_f64().map(Scalar::Number),
+                ValueKind::String => value.as_str().map(Scalar::String),
+                ValueKind::Array | ValueKind::Object => None,
+            })
+            .collect(),
+        Expr::BinaryOp { .. } | Expr::FilterFunc(_) => Vec::new(),
+    }
+}
+
+fn number_to_f64(n: &Number) -> f64 {
+    match n {
+        Number::Int64(n) => *n as f64,
+        Number::UInt64(n) => *n as f64,
+        Number::Float64(n) => *n,
+    }
+}
+
+fn compare(lhs: &Scalar, op: &BinaryOperator, rhs: &Scalar) -> bool {
+    let Some(ordering) = lhs.partial_cmp(rhs) else {
+        return false;
+    };
+    match op {
+        BinaryOperator::Eq => ordering == Ordering::Equal,
+        BinaryOperator::NotEq => ordering != Ordering::Equal,
+        BinaryOperator::Lt => ordering == Ordering::Less,
+        BinaryOperator::Lte => ordering != Ordering::Greater,
+        BinaryOperator::Gt => ordering == Ordering::Greater,
+        BinaryOperator::Gte => ordering != Ordering::Less,
+        BinaryOperator::And | BinaryOperator::Or => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonb::jsonpath::{parse_json_path, Mode, Selector};
+
+    use super::*;
+    use crate::decoder::{decode, decode_slice};
+    use crate::parser::parse_value;
+
+    /// Writes a JSON text as `jsonb` returns it, keys sorted and deduplicated,
+    /// with numbers rounded through `f64` as `Jsonc` stores them.
+    fn normalize(json: &str) -> String {
+        let jsonb = jsonb::parse_value(json.as_bytes()).unwrap().to_vec();
+        decode(&parse_value(jsonb::to_string(&jsonb).as_bytes()))
+    }
+
+    /// Runs `jsonb`'s selector in `mode`, returning each selected value.
+    fn jsonb_select(jsonb: &[u8], path: &JsonPath, mode: Mode) -> Vec<String> {
+        let selector = Selector::new(path.clone(), mode);
+        let mut data = Vec::new();
+        let mut offsets = Vec::new();
+        selector.select(jsonb, &mut data, &mut offsets).unwrap();
+        // The result of a predicate is written without an offset.
+        if offsets.is_empty() && !data.is_empty() {
+            offsets.push(data.len() as u64);
+        }
+        let mut start = 0;
+        offsets
+            .into_iter()
+            .map(|end| {
+                let value = jsonb::to_string(&data[start..end as usize]);
+                start = end as usize;
+                decode(&parse_value(value.as_bytes()))
+            })
+            .collect()
+    }
+
+    /// Evaluates `path` over `json` both here and with `jsonb`, asserting
+    /// that all four entry points agree, and returns the values selected.
+    fn check(json: &str, path: &str) -> Vec<String> {
+        let jsonc = parse_value(json.as_bytes());
+        let jsonb = jsonb::parse_value(json.as_bytes()).unwrap().to_vec();
+        let path = parse_json_path(path.as_bytes()).unwrap();
+        let selector = Selector::new(path.clone(), Mode::All);
+
+        let values: Vec<_> = jsonc
+            .jsonb_path_query(&path)
+            .into_iter()
+            .map(|value| normalize(&decode_slice(value)))
+            .collect();
+        assert_eq!(values, jsonb_select(&jsonb, &path, Mode::All), "{}", path);
+        assert_eq!(
+            jsonc
+                .jsonb_path_query_first(&path)
+                .map(|value| normalize(&decode_slice(value))),
+            jsonb_select(&jsonb, &path, Mode::First).into_iter().next(),
+            "{}",
+            path
+        );
+        assert_eq!(
+            jsonc.jsonb_path_exists(&path),
+            selector.exists(&jsonb).unwrap(),
+            "{}",
+            path
+        );
+        match selector.predicate_match(&jsonb) {
+            Ok(matched) => assert_eq!(jsonc.jsonb_path_match(&path).unwrap(), matched),
+            Err(_) => assert!(jsonc.jsonb_path_match(&path).is_err(), "{}", path),
+        }
+        values
+    }
+
+    #[test]
+    fn test_jsonb_path_query() {
+        let json = r#"{"b":[1,2,3,4],"a":{"y":1,"x":2,"y":3},"s":"str","n":null}"#;
+        assert_eq!(check(json, "$"), [normalize(json)]);
+        assert_eq!(
+            check(json, "$.*"),
+            [r#"{"x":2,"y":3}"#, "[1,2,3,4]", "null", r#""str""#]
+        );
+        assert_eq!(check(json, "$.a.*"), ["2", "3"]);
+        assert_eq!(check(json, "$.a.y"), ["3"]);
+        assert_eq!(check(json, r#"$.a."y""#), ["3"]);
+        assert_eq!(check(json, "$.s[*]"), [r#""str""#]);
+        assert_eq!(check(json, "$.a[*]"), [r#"{"x":2,"y":3}"#]);
+        assert_eq!(check(json, "$.b[*]"), ["1", "2", "3", "4"]);
+        assert!(check(json, "$.b.a").is_empty());
+        assert!(check(json, "$.b.*").is_empty());
+        assert!(check(json, "$.s.*").is_empty());
+        assert!(check(json, "$.missing").is_empty());
+        assert!(check(json, "$.a[0]").is_empty());
+        assert!(check(json, "$.n[0]").is_empty());
+
+        assert_eq!(check(json, "$.b[last, 0, 4, last - 1]"), ["4", "1", "3"]);
+        assert_eq!(check(json, "$.b[1 to last]"), ["2", "3", "4"]);
+        assert_eq!(check(json, "$.b[-5 to 9]"), ["1", "2", "3", "4"]);
+        assert_eq!(check(json, "$.b[last - 9 to 1]"), ["1", "2"]);
+        assert!(check(json, "$.b[2 to 1]").is_empty());
+        assert!(check(json, "$.b[4 to 9]").is_empty());
+        assert!(check("[]", "$[0 to last]").is_empty());
+        assert!(check("[]", "$[last]").is_empty());
+    }
+
+    #[test]
+    fn test_jsonb_path_filter() {
+        let json =
+            r#"[{"n":1,"s":"abc"},{"n":"2","s":"xyz"},{"n":[3,0]},{"m":null},7,"7",null,true]"#;
+        assert_eq!(
+            check(json, "$[*] ? (@.n >= 1)"),
+            [r#"{"n":1,"s":"abc"}"#, r#"{"n":"2","s":"xyz"}"#]
+        );
+        assert_eq!(check(json, "$[*] ? (@.n < 3)"), [r#"{"n":1,"s":"abc"}"#]);
+        assert_eq!(
+            check(json, r#"$[*] ? (@.n == "2")"#),
+            [r#"{"n":"2","s":"xyz"}"#]
+        );
+        assert_eq!(check(json, "$[*] ? (exists(@.m))"), [r#"{"m":null}"#]);
+        assert_eq!(
+            check(json, "$[*] ? (@.n == 1 || @.m == null)"),
+            [r#"{"n":1,"s":"abc"}"#, r#"{"m":null}"#]
+        );
+        assert_eq!(
+            check(json, r#"$[*] ? (@.n > 0 && @.s > "b").s"#),
+            [r#""xyz""#]
+        );
+        // `$` in a filter is the root, an array here, so it has no field `n`.
+        assert!(check(json, "$[*] ? ($.n == 1)").is_empty());
+
+        // Scalars of different types compare by type first.
+        assert_eq!(check(json, "$[*] ? (@ > 1)"), ["7", r#""7""#]);
+        assert_eq!(check(json, "$[*] ? (@ < 1)"), ["null", "true"]);
+        assert_eq!(check(json, "$[*] ? (@ == null)"), ["null"]);
+        assert_eq!(check(json, r#"$[*] ? (@ != "7")"#), ["7", "null", "true"]);
+        assert_eq!(check(json, "$[*] ? (@ > false)"), ["7", r#""7""#, "true"]);
+
+        let json = r#"{"a":[1,5],"b":"x","c":{"d":1,"d":2}}"#;
+        assert_eq!(check(json, "$.a[*] > 4"), ["true"]);
+        assert_eq!(check(json, "$.a[*] > 5"), ["false"]);
+        assert_eq!(check(json, "$.b > 5"), ["true"]);
+        assert_eq!(check(json, "$.c.d == 2"), ["true"]);
+        assert_eq!(check(json, "$.c.d == 1"), ["false"]);
+        assert_eq!(check(json, "$.c ? (@.d == 2)"), [r#"{"d":2}"#]);
+        assert!(parse_value(json.as_bytes())
+            .jsonb_path_match(&parse_json_path(b"$.a").unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_jsonb_path_twitter() {
+        let json = std::fs::read_to_string("data/twitter.json").unwrap();
+        for path in [
+            "$.search_metadata.*",
+            "$.statuses[0].user.screen_name",
+            "$.statuses[*].user.id",
+            "$.statuses[last].id_str",
+            "$.statuses[last - 2 to last].text",
+            "$.statuses[0, 2 to 3].entities.*",
+            "$.statuses[*].entities.hashtags[*].text",
+            "$.statuses[*].user.entities.url.urls[*].expanded_url",
+            "$.statuses[*] ? (@.retweet_count > 0).id_str",
+            "$.statuses[*] ? (exists(@.retweeted_status)).retweeted_status.user.name",
+            r#"$.statuses[*].user ? (@.followers_count >= 100 && @.lang == "ja").screen_name"#,
+            r#"$.statuses[*].metadata ? (@.iso_language_code != "ja")"#,
+            "$.statuses[*].place[*]",
+            "$.statuses[1].*[*]",
+            "$.search_metadata.count == 100",
+            "$.statuses[*].retweet_count > 100",
+            "$.nothing",
+        ] {
+            check(&json, path);
+        }
+        assert_eq!(check(&json, "$.search_metadata.count"), ["100"]);
+        assert_eq!(check(&json, "$.statuses[*].id_str").len(), 100);
+    }
+}
@@ -0,0 +1,971 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::path::{NormalizedPath, PathItem};
+use crate::slice::JsoncSlice;
+use crate::value::{Jsonc, Node};
+
+/// A parsed RFC 9535 JSONPath query, such as
+/// `$.statuses[?@.retweet_count > 0].user.screen_name`.
+///
+/// All segment and selector kinds are supported: child and descendant (`..`)
+/// segments with name, wildcard, index, slice and filter selectors. Filters
+/// may call `length`, `count` and `value`; the regular expression functions
+/// `match` and `search` are not supported.
+#[derive(Debug, Clone)]
+pub struct JsonPathQuery {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Debug, Clone)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    Filter(LogicalExpr),
+}
+
+#[derive(Debug, Clone)]
+enum LogicalExpr {
+    Or(Box<LogicalExpr>, Box<LogicalExpr>),
+    And(Box<LogicalExpr>, Box<LogicalExpr>),
+    Not(Box<LogicalExpr>),
+    Comparison(Comparable, CompareOp, Comparable),
+    /// True if the query selects at least one node.
+    Exists(FilterQuery),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A query inside a filter, starting from the current node (`@`) or the root (`$`).
+#[derive(Debug, Clone)]
+struct FilterQuery {
+    relative: bool,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Comparable {
+    Literal(Jsonc),
+    /// A query that selects at most one node.
+    Query(FilterQuery),
+    Function(Function),
+}
+
+#[derive(Debug, Clone)]
+enum Function {
+    Length(Box<Comparable>),
+    Count(FilterQuery),
+    Value(FilterQuery),
+}
+
+/// The result of evaluating a `Comparable`.
+#[derive(Debug, Clone, Copy)]
+enum Operand<'a> {
+    Nothing,
+    Node(JsoncSlice<'a>),
+    Number(f64),
+}
+
+impl JsonPathQuery {
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut parser = Parser::new(query);
+        if !parser.eat('$') {
+            return parser.error("expected `$`");
+        }
+        let segments = parser.parse_segments()?;
+        if parser.pos < query.len() {
+            return parser.error("unexpected trailing characters");
+        }
+        Ok(Self { segments })
+    }
+
+    /// Selects the matching values of `json` in the order RFC 9535 defines,
+    /// each with its location.
+    pub fn query<'a>(
+        &self,
+        json: JsoncSlice<'a>,
+    ) -> impl Iterator<Item = (NormalizedPath<'a>, JsoncSlice<'a>)> {
+        select(&self.segments, json, json)
+            .into_iter()
+            .map(|(path, node)| (path.into(), node))
+    }
+}
+
+impl FromStr for JsonPathQuery {
+    type Err = Error;
+
+    fn from_str(query: &str) -> Result<Self> {
+        Self::parse(query)
+    }
+}
+
+impl<'a> JsoncSlice<'a> {
+    /// Runs a JSONPath query, see [`JsonPathQuery::query`].
+    pub fn query(
+        &self,
+        query: &JsonPathQuery,
+    ) -> impl Iterator<Item = (NormalizedPath<'a>, JsoncSlice<'a>)> {
+        query.query(*self)
+    }
+}
+
+type Located<'a> = (Vec<PathItem<'a>>, JsoncSlice<'a>);
+
+fn select<'a>(
+    segments: &[Segment],
+    root: JsoncSlice<'a>,
+    start: JsoncSlice<'a>,
+) -> Vec<Located<'a>> {
+    let mut nodes = vec![(Vec::new(), start)];
+    for segment in segments {
+        let mut selected = Vec::new();
+        for (mut path, node) in nodes {
+            match segment {
+                Segment::Child(selectors) => {
+                    apply_selectors(selectors, root, &path, node, &mut selected);
+                }
+                Segment::Descendant(selectors) => {
+                    descend(selectors, root, &mut path, node, &mut selected);
+                }
+            }
+        }
+        nodes = selected;
+    }
+    nodes
+}
+
+/// Applies `selectors` to `node` and then to each of its descendants, parents
+/// before children and siblings in document order.
+fn descend<'a>(
+    selectors: &[Selector],
+    root: JsoncSlice<'a>,
+    path: &mut Vec<PathItem<'a>>,
+    node: JsoncSlice<'a>,
+    selected: &mut Vec<Located<'a>>,
+) {
+    apply_selectors(selectors, root, path, node, selected);
+    for (item, child) in children(node) {
+        path.push(item);
+        descend(selectors, root, path, child, selected);
+        path.pop();
+    }
+}
+
+fn apply_selectors<'a>(
+    selectors: &[Selector],
+    root: JsoncSlice<'a>,
+    path: &[PathItem<'a>],
+    node: JsoncSlice<'a>,
+    selected: &mut Vec<Located<'a>>,
+) {
+    let mut push = |item: PathItem<'a>, child: JsoncSlice<'a>| {
+        let mut child_path = path.to_vec();
+        child_path.push(item);
+        selected.push((child_path, child));
+    };
+    for selector in selectors {
+        match selector {
+            Selector::Name(name) => {
+                if let Some((key, child)) = node
+                    .object_iter()
+                    .and_then(|mut entries| entries.find(|(key, _)| key == name))
+                {
+                    push(PathItem::Key(key), child);
+                }
+            }
+            Selector::Wildcard => {
+                for (item, child) in children(node) {
+                    push(item, child);
+                }
+            }
+            Selector::Index(idx) => {
+                let Some(len) = node.array_iter().map(|elements| elements.count()) else {
+                    continue;
+                };
+                let idx = if *idx < 0 { len as i64 + idx } else { *idx };
+                if (0..len as i64).contains(&idx) {
+                    push(
+                        PathItem::Index(idx as usize),
+                        node.get_by_idx(idx as usize).unwrap(),
+                    );
+                }
+            }
+            Selector::Slice { start, end, step } => {
+                let Some(elements) = node.array_iter() else {
+                    continue;
+                };
+                let elements: Vec<_> = elements.collect();
+                for idx in slice_indices(*start, *end, *step, elements.len()) {
+                    push(PathItem::Index(idx), elements[idx]);
+                }
+            }
+            Selector::Filter(expr) => {
+                for (item, child) in children(node) {
+                    if expr.test(root, child) {
+                        push(item, child);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn children(node: JsoncSlice) -> Vec<(PathItem, JsoncSlice)> {
+    match node.nodes.first() {
+        Some(Node::StartArray) => node
+            .array_iter()
+            .unwrap()
+            .enumerate()
+            .map(|(idx, child)| (PathItem::Index(idx), child))
+            .collect(),
+        Some(Node::StartObject) => node
+            .object_iter()
+            .unwrap()
+            .map(|(key, child)| (PathItem::Key(key), child))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The array indices selected by `start:end:step`, following RFC 9535 section 2.3.4.2.
+fn slice_indices(
+    start: Option<i64>,
+    end: Option<i64>,
+    step: Option<i64>,
+    len: usize,
+) -> Vec<usize> {
+    let len = len as i64;
+    let step = step.unwrap_or(1);
+    let normalize = |idx: i64| if idx >= 0 { idx } else { len + idx };
+    let mut indices = Vec::new();
+    match step.cmp(&0) {
+        Ordering::Greater => {
+            let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+            let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+            let mut idx = lower;
+            while idx < upper {
+                indices.push(idx as usize);
+                idx += step;
+            }
+        }
+        Ordering::Less => {
+            let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+            let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+            let mut idx = upper;
+            while lower < idx {
+                indices.push(idx as usize);
+                idx += step;
+            }
+        }
+        Ordering::Equal => {}
+    }
+    indices
+}
+
+impl LogicalExpr {
+    fn test(&self, root: JsoncSlice, current: JsoncSlice) -> bool {
+        match self {
+            LogicalExpr::Or(lhs, rhs) => lhs.test(root, current) || rhs.test(root, current),
+            LogicalExpr::And(lhs, rhs) => lhs.test(root, current) && rhs.test(root, current),
+            LogicalExpr::Not(expr) => !expr.test(root, current),
+            LogicalExpr::Comparison(lhs, op, rhs) => {
+                let lhs = lhs.eval(root, current);
+                let rhs = rhs.eval(root, current);
+                match op {
+                    CompareOp::Eq => operand_eq(lhs, rhs),
+                    CompareOp::Ne => !operand_eq(lhs, rhs),
+                    CompareOp::Lt => operand_lt(lhs, rhs),
+                    CompareOp::Le => operand_lt(lhs, rhs) || operand_eq(lhs, rhs),
+                    CompareOp::Gt => operand_lt(rhs, lhs),
+                    CompareOp::Ge => operand_lt(rhs, lhs) || operand_eq(lhs, rhs),
+                }
+            }
+            LogicalExpr::Exists(query) => !query.eval(root, current).is_empty(),
+        }
+    }
+}
+
+impl FilterQuery {
+    fn eval<'a>(&self, root: JsoncSlice<'a>, current: JsoncSlice<'a>) -> Vec<Located<'a>> {
+        let start = if self.relative { current } else { root };
+        select(&self.segments, root, start)
+    }
+
+    /// Whether the query can select at most one node, using only name and
+    /// index selectors in child segments.
+    fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| match segment {
+            Segment::Child(selectors) => {
+                matches!(selectors[..], [Selector::Name(_)] | [Selector::Index(_)])
+            }
+            Segment::Descendant(_) => false,
+        })
+    }
+
+    fn single<'a>(&self, root: JsoncSlice<'a>, current: JsoncSlice<'a>) -> Operand<'a> {
+        match &self.eval(root, current)[..] {
+            [(_, node)] => Operand::Node(*node),
+            _ => Operand::Nothing,
+        }
+    }
+}
+
+impl Comparable {
+    fn eval<'a>(&'a self, root: JsoncSlice<'a>, current: JsoncSlice<'a>) -> Operand<'a> {
+        match self {
+            Comparable::Literal(literal) => Operand::Node(literal.as_slice()),
+            Comparable::Query(query) => query.single(root, current),
+            Comparable::Function(Function::Length(arg)) => match arg.eval(root, current) {
+                Operand::Node(node) => match node.as_str() {
+                    Some(s) => Operand::Number(s.chars().count() as f64),
+                    None => node
+                        .len()
+                        .map_or(Operand::Nothing, |len| Operand::Number(len as f64)),
+                },
+                _ => Operand::Nothing,
+            },
+            Comparable::Function(Function::Count(query)) => {
+                Operand::Number(query.eval(root, current).len() as f64)
+            }
+            Comparable::Function(Function::Value(query)) => query.single(root, current),
+        }
+    }
+}
+
+fn operand_number(operand: Operand) -> Option<f64> {
+    match operand {
+        Operand::Number(n) => Some(n),
+        Operand::Node(node) => node.as_f64(),
+        Operand::Nothing => None,
+    }
+}
+
+fn operand_eq(lhs: Operand, rhs: Operand) -> bool {
+    match (lhs, rhs) {
+        (Operand::Nothing, Operand::Nothing) => true,
+        (Operand::Node(lhs), Operand::Node(rhs)) => lhs.semantic_eq(&rhs),
+        (Operand::Number(n), other) | (other, Operand::Number(n)) => {
+            operand_number(other) == Some(n)
+        }
+        _ => false,
+    }
+}
+
+/// `<` is only defined between two numbers or two strings; every other
+/// pairing is false.
+fn operand_lt(lhs: Operand, rhs: Operand) -> bool {
+    if let (Some(lhs), Some(rhs)) = (operand_number(lhs), operand_number(rhs)) {
+        return lhs < rhs;
+    }
+    match (lhs, rhs) {
+        (Operand::Node(lhs), Operand::Node(rhs)) => match (lhs.as_str(), rhs.as_str()) {
+            (Some(lhs), Some(rhs)) => lhs < rhs,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// The operand of a comparison or the whole of a test expression, before it
+/// is known which of the two it is.
+enum Term {
+    Query(FilterQuery),
+    Function(Function),
+    Literal(Jsonc),
+}
+
+/// A cursor over a query string, shared with the dotted path syntax of
+/// [`JsonPath`](crate::path::JsonPath).
+pub(crate) struct Parser<'s> {
+    src: &'s str,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    pub(crate) fn new(src: &'s str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    pub(crate) fn error<T>(&self, msg: &str) -> Result<T> {
+        Err(Error::Syntax {
+            msg: msg.to_string(),
+            pos: self.pos,
+        })
+    }
+
+    pub(crate) fn rest(&self) -> &'s str {
+        &self.src[self.pos..]
+    }
+
+    pub(crate) fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    pub(crate) fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    pub(crate) fn eat(&mut self, c: char) -> bool {
+        self.eat_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    pub(crate) fn eat_str(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            self.error(&format!("expected `{}`", c))
+        }
+    }
+
+    pub(crate) fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_segments(&mut self) -> Result<Vec<Segment>> {
+        let mut segments = Vec::new();
+        loop {
+            let save = self.pos;
+            self.skip_ws();
+            if self.eat_str("..") {
+                let selectors = if self.peek() == Some('[') {
+                    self.parse_bracketed()?
+                } else if self.eat('*') {
+                    vec![Selector::Wildcard]
+                } else {
+                    vec![Selector::Name(self.parse_member_name()?)]
+                };
+                segments.push(Segment::Descendant(selectors));
+            } else if self.eat('.') {
+                let selector = if self.eat('*') {
+                    Selector::Wildcard
+                } else {
+                    Selector::Name(self.parse_member_name()?)
+                };
+                segments.push(Segment::Child(vec![selector]));
+            } else if self.peek() == Some('[') {
+                segments.push(Segment::Child(self.parse_bracketed()?));
+            } else {
+                self.pos = save;
+                return Ok(segments);
+            }
+        }
+    }
+
+    fn parse_member_name(&mut self) -> Result<String> {
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' || !c.is_ascii() => {}
+            _ => return self.error("expected a member name"),
+        }
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()) {
+                break;
+            }
+            self.bump();
+        }
+        Ok(self.src[start..self.pos].to_string())
+    }
+
+    fn parse_bracketed(&mut self) -> Result<Vec<Selector>> {
+        self.expect('[')?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_ws();
+            selectors.push(self.parse_selector()?);
+            self.skip_ws();
+            if self.eat(']') {
+                return Ok(selectors);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn parse_selector(&mut self) -> Result<Selector> {
+        match self.peek() {
+            Some('\'' | '"') => Ok(Selector::Name(self.parse_string()?)),
+            Some('*') => {
+                self.bump();
+                Ok(Selector::Wildcard)
+            }
+            Some('?') => {
+                self.bump();
+                self.skip_ws();
+                Ok(Selector::Filter(self.parse_or()?))
+            }
+            _ => {
+                let start = self.parse_opt_int()?;
+                self.skip_ws();
+                if !self.eat(':') {
+                    return match start {
+                        Some(idx) => Ok(Selector::Index(idx)),
+                        None => self.error("expected a selector"),
+                    };
+                }
+                self.skip_ws();
+                let end = self.parse_opt_int()?;
+                self.skip_ws();
+                let mut step = None;
+                if self.eat(':') {
+                    self.skip_ws();
+                    step = self.parse_opt_int()?;
+                }
+                Ok(Selector::Slice { start, end, step })
+            }
+        }
+    }
+
+    /// Parses an integer without leading zeros in the I-JSON range, if one is next.
+    pub(crate) fn parse_opt_int(&mut self) -> Result<Option<i64>> {
+        let start = self.pos;
+        let negative = self.eat('-');
+        let digits = self.rest().bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            if negative {
+                return self.error("expected digits");
+            }
+            return Ok(None);
+        }
+        let text = &self.rest()[..digits];
+        if (text.starts_with('0') && (digits > 1 || negative)) || digits > 16 {
+            return self.error("invalid integer");
+        }
+        self.pos += digits;
+        let n: i64 = self.src[start..self.pos].parse().unwrap();
+        if n.unsigned_abs() > (1 << 53) - 1 {
+            self.pos = start;
+            return self.error("integer out of range");
+        }
+        Ok(Some(n))
+    }
+
+    pub(crate) fn parse_string(&mut self) -> Result<String> {
+        let quote = self.bump().unwrap();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None => return self.error("unterminated string"),
+                Some(c) if c == quote => return Ok(s),
+                Some('\\') => match self.bump() {
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('/') => s.push('/'),
+                    Some('\\') => s.push('\\'),
+                    Some(c) if c == quote => s.push(c),
+                    Some('u') => s.push(self.parse_unicode_escape()?),
+                    _ => return self.error("invalid escape"),
+                },
+                Some('\u{0}'..='\u{1f}') => return self.error("control character in string"),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32> {
+        let hex = self.rest().get(..4).unwrap_or("");
+        match u32::from_str_radix(hex, 16) {
+            Ok(n) if hex.len() == 4 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                self.pos += 4;
+                Ok(n)
+            }
+            _ => self.error("expected four hex digits"),
+        }
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char> {
+        let high = self.parse_hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !self.eat_str("\\u") {
+                return self.error("unpaired surrogate");
+            }
+            let low = self.parse_hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return self.error("unpaired surrogate");
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        match char::from_u32(code) {
+            Some(c) => Ok(c),
+            None => self.error("unpaired surrogate"),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<LogicalExpr> {
+        let mut expr = self.parse_and()?;
+        loop {
+            let save = self.pos;
+            self.skip_ws();
+            if !self.eat_str("||") {
+                self.pos = save;
+                return Ok(expr);
+            }
+            self.skip_ws();
+            expr = LogicalExpr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+    }
+
+    fn parse_and(&mut self) -> Result<LogicalExpr> {
+        let mut expr = self.parse_basic()?;
+        loop {
+            let save = self.pos;
+            self.skip_ws();
+            if !self.eat_str("&&") {
+                self.pos = save;
+                return Ok(expr);
+            }
+            self.skip_ws();
+            expr = LogicalExpr::And(Box::new(expr), Box::new(self.parse_basic()?));
+        }
+    }
+
+    fn parse_basic(&mut self) -> Result<LogicalExpr> {
+        if self.eat('!') {
+            self.skip_ws();
+            let expr = if self.peek() == Some('(') {
+                self.parse_paren()?
+            } else {
+                match self.parse_term()? {
+                    Term::Query(query) => LogicalExpr::Exists(query),
+                    _ => return self.error("expected a query after `!`"),
+                }
+            };
+            return Ok(LogicalExpr::Not(Box::new(expr)));
+        }
+        if self.peek() == Some('(') {
+            return self.parse_paren();
+        }
+        let lhs = self.parse_term()?;
+        let save = self.pos;
+        self.skip_ws();
+        let Some(op) = self.parse_compare_op() else {
+            self.pos = save;
+            return match lhs {
+                Term::Query(query) => Ok(LogicalExpr::Exists(query)),
+                Term::Function(_) => self.error("function result must be compared"),
+                Term::Literal(_) => self.error("expected a comparison"),
+            };
+        };
+        self.skip_ws();
+        let lhs = self.comparable(lhs)?;
+        let rhs = self.parse_term()?;
+        let rhs = self.comparable(rhs)?;
+        Ok(LogicalExpr::Comparison(lhs, op, rhs))
+    }
+
+    fn parse_paren(&mut self) -> Result<LogicalExpr> {
+        self.expect('(')?;
+        self.skip_ws();
+        let expr = self.parse_or()?;
+        self.skip_ws();
+        self.expect(')')?;
+        Ok(expr)
+    }
+
+    fn parse_compare_op(&mut self) -> Option<CompareOp> {
+        let ops = [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ];
+        ops.into_iter()
+            .find(|(s, _)| self.eat_str(s))
+            .map(|(_, op)| op)
+    }
+
+    fn comparable(&self, term: Term) -> Result<Comparable> {
+        match term {
+            Term::Query(query) if !query.is_singular() => {
+                self.error("only singular queries can be compared")
+            }
+            Term::Query(query) => Ok(Comparable::Query(query)),
+            Term::Function(function) => Ok(Comparable::Function(function)),
+            Term::Literal(literal) => Ok(Comparable::Literal(literal)),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Term> {
+        match self.peek() {
+            Some('@') => {
+                self.bump();
+                let segments = self.parse_segments()?;
+                Ok(Term::Query(FilterQuery {
+                    relative: true,
+                    segments,
+                }))
+            }
+            Some('$') => {
+                self.bump();
+                let segments = self.parse_segments()?;
+                Ok(Term::Query(FilterQuery {
+                    relative: false,
+                    segments,
+                }))
+            }
+            Some('\'' | '"') => {
+                let s = self.parse_string()?;
+                Ok(Term::Literal(Jsonc::new_with_values(
+                    vec![Node::String],
+                    vec![s],
+                    vec![],
+                )))
+            }
+            Some('-' | '0'..='9') => Ok(Term::Literal(self.parse_number()?)),
+            Some('a'..='z') => {
+                for (word, node) in [
+                    ("true", Node::True),
+                    ("false", Node::False),
+                    ("null", Node::Null),
+                ] {
+                    if self.eat_str(word) {
+                        return Ok(Term::Literal(Jsonc::new_with_values(
+                            vec![node],
+                            vec![],
+                            vec![],
+                        )));
+                    }
+                }
+                Ok(Term::Function(self.parse_function()?))
+            }
+            _ => self.error("expected a query, literal or function"),
+        }
+    }
+
+    pub(crate) fn parse_number(&mut self) -> Result<Jsonc> {
+        let start = self.pos;
+        self.eat('-');
+        let int_digits = self.rest().bytes().take_while(u8::is_ascii_digit).count();
+        if int_digits == 0 || (int_digits > 1 && self.rest().starts_with('0')) {
+            return self.error("invalid number");
+        }
+        self.pos += int_digits;
+        if self.eat('.') {
+            let digits = self.rest().bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 {
+                return self.error("invalid number");
+            }
+            self.pos += digits;
+        }
+        if self.eat('e') || self.eat('E') {
+            if !self.eat('+') {
+                self.eat('-');
+            }
+            let digits = self.rest().bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 {
+                return self.error("invalid number");
+            }
+            self.pos += digits;
+        }
+        let n: f64 = self.src[start..self.pos].parse().unwrap();
+        Ok(Jsonc::new_with_values(vec![Node::Number], vec![], vec![n]))
+    }
+
+    fn parse_function(&mut self) -> Result<Function> {
+        let start = self.pos;
+        while matches!(self.peek(), Some('a'..='z' | '0'..='9' | '_')) {
+            self.bump();
+        }
+        let name = &self.src[start..self.pos];
+        self.expect('(')?;
+        self.skip_ws();
+        let function = match name {
+            "length" => {
+                let arg = self.parse_term()?;
+                Function::Length(Box::new(self.comparable(arg)?))
+            }
+            "count" | "value" => {
+                let Term::Query(query) = self.parse_term()? else {
+                    return self.error("expected a query argument");
+                };
+                if name == "count" {
+                    Function::Count(query)
+                } else {
+                    Function::Value(query)
+                }
+            }
+            "match" | "search" => {
+                self.pos = start;
+                return self.error("regular expression functions are not supported");
+            }
+            _ => {
+                self.pos = start;
+                return self.error("unknown function");
+            }
+        };
+        self.skip_ws();
+        self.expect(')')?;
+        Ok(function)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode_slice;
+    use crate::parser::parse_value;
+
+    fn run(json: &Jsonc, query: &str) -> Vec<(String, String)> {
+        JsonPathQuery::parse(query)
+            .unwrap()
+            .query(json.as_slice())
+            .map(|(path, node)| (path.to_string(), decode_slice(node)))
+            .collect()
+    }
+
+    fn values(json: &Jsonc, query: &str) -> Vec<String> {
+        run(json, query)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    #[test]
+    fn test_jsonpath_selectors() {
+        let json = parse_value(
+            br#"{"store":{"book":[
+                {"category":"reference","author":"Nigel Rees","price":8.95},
+                {"category":"fiction","author":"Evelyn Waugh","price":12.99},
+                {"category":"fiction","author":"Herman Melville","isbn":"0-553-21311-3","price":8.99},
+                {"category":"fiction","author":"J. R. R. Tolkien","isbn":"0-395-19395-8","price":22.99}
+            ],"bicycle":{"color":"red","price":399}}}"#,
+        );
+        let authors = [
+            r#""Nigel Rees""#,
+            r#""Evelyn Waugh""#,
+            r#""Herman Melville""#,
+            r#""J. R. R. Tolkien""#,
+        ];
+        assert_eq!(values(&json, "$.store.book[*].author"), authors);
+        assert_eq!(values(&json, "$..author"), authors);
+        assert_eq!(
+            values(&json, "$.store..price"),
+            ["8.95", "12.99", "8.99", "22.99", "399"]
+        );
+        assert_eq!(
+            run(&json, "$..book[-1].author"),
+            [(
+                "$['store']['book'][3]['author']".to_string(),
+                authors[3].to_string()
+            )]
+        );
+        assert_eq!(values(&json, "$..book[0, 1].author"), &authors[..2]);
+        assert_eq!(values(&json, "$..book[:2].author"), &authors[..2]);
+        assert_eq!(values(&json, "$..book[?@.isbn].author"), &authors[2..]);
+        assert_eq!(
+            values(&json, "$..book[?@.price < 10].author"),
+            [authors[0], authors[2]]
+        );
+        assert_eq!(
+            values(&json, r#"$["store"]['bicycle'].color"#),
+            [r#""red""#]
+        );
+        assert_eq!(values(&json, "$..*").len(), 23);
+
+        let json = parse_value(br#"["a","b","c","d","e","f","g"]"#);
+        assert_eq!(values(&json, "$[1:3]"), [r#""b""#, r#""c""#]);
+        assert_eq!(values(&json, "$[5:]"), [r#""f""#, r#""g""#]);
+        assert_eq!(values(&json, "$[1:5:2]"), [r#""b""#, r#""d""#]);
+        assert_eq!(values(&json, "$[5:1:-2]"), [r#""f""#, r#""d""#]);
+        assert_eq!(values(&json, "$[::-1]").len(), 7);
+        assert_eq!(values(&json, "$[::0]").len(), 0);
+        assert_eq!(values(&json, "$[-8]").len(), 0);
+    }
+
+    #[test]
+    fn test_jsonpath_filters() {
+        let json = parse_value(
+            br#"{"a":[3,5,1,2,4,6,{"b":"j"},{"b":"k"},{"b":{}},{"b":"kilo"}],
+                "o":{"p":1,"q":2,"r":3,"s":5,"t":{"u":6}},"e":"f"}"#,
+        );
+        assert_eq!(
+            run(&json, "$.a[?@.b == 'kilo']"),
+            [("$['a'][9]".to_string(), r#"{"b":"kilo"}"#.to_string())]
+        );
+        assert_eq!(values(&json, "$.a[?@>3.5]"), ["5", "4", "6"]);
+        assert_eq!(values(&json, "$.a[?@.b]").len(), 4);
+        assert_eq!(values(&json, "$[?@.*]").len(), 2);
+        assert_eq!(values(&json, "$.o[?@<3, ?@<3]"), ["1", "2", "1", "2"]);
+        assert_eq!(
+            values(&json, r#"$.a[?@<2 || @.b == "k"]"#),
+            ["1", r#"{"b":"k"}"#]
+        );
+        assert_eq!(values(&json, "$.o[?@>1 && @<4]"), ["2", "3"]);
+        assert_eq!(values(&json, "$.a[?@.b == $.x]").len(), 6);
+        assert_eq!(values(&json, "$.a[?@ == @]").len(), 10);
+        assert_eq!(values(&json, "$.a[?!(@ > 2)]").len(), 6);
+        assert_eq!(values(&json, "$.a[?length(@.b) == 4]"), [r#"{"b":"kilo"}"#]);
+        assert_eq!(values(&json, "$[?count(@.*) > 5]"), values(&json, "$.a"));
+        assert_eq!(values(&json, "$.o[?value(@..u) == 6]"), [r#"{"u":6}"#]);
+    }
+
+    #[test]
+    fn test_jsonpath_syntax() {
+        for query in [
+            "$.a[?@.b]",
+            "$..[0]",
+            "$[ 'a' , \"b\" ]",
+            "$.a[?(@.b == 1) && !@.c]",
+            "$['\\u263a\\'']",
+        ] {
+            assert!(JsonPathQuery::parse(query).is_ok(), "{}", query);
+        }
+        for query in [
+            "a",
+            "$.",
+            "$[01]",
+            "$[-0]",
+            "$[?@.* == 1]",
+            "$[?1]",
+            "$[?length(@.*)]",
+            "$[?match(@, 'a')]",
+            "$[?@.b == {}]",
+            "$['a' 'b']",
+            "$ ",
+        ] {
+            assert!(JsonPathQuery::parse(query).is_err(), "{}", query);
+        }
+
+        let json = parse_value(br#"{"it's":{"\n":1}}"#);
+        assert_eq!(run(&json, "$.*.*")[0].0, r"$['it\'s']['\n']");
+    }
+}
@@ -1,5 +1,19 @@
+pub mod canonical;
+pub mod compare;
 pub mod decoder;
+pub mod error;
+pub mod extract;
+pub mod filter;
+pub mod jq;
+pub mod jsonb_path;
+pub mod jsonpath;
+pub mod ops;
 pub mod parser;
+pub mod path;
+pub mod pointer;
 pub mod value;
 pub mod serde;
+pub mod seq;
 pub mod slice;
+pub mod summary;
+pub mod visitor;
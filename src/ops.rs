@@ -0,0 +1,219 @@
+use crate::path::JsonPath;
+use crate::slice::{JsoncSlice, ValueKind};
+use crate::value::Jsonc;
+
+impl JsoncSlice<'_> {
+    /// Tests whether this value contains `other`, like the PostgreSQL
+    /// `jsonb` operator `@>`:
+    ///
+    /// - A scalar contains only an equal scalar.
+    /// - An object contains an object if every entry of the other object has
+    ///   a matching key here whose value contains the entry's value.
+    /// - An array contains an array if every element of the other array is
+    ///   contained by some element here, regardless of order and repetition.
+    /// - As a special case, an array contains a scalar that is one of its
+    ///   elements, but only at the top level.
+    ///
+    /// The values are compared in place, and the test stops at the first
+    /// entry or element that is not contained. When an object repeats a key
+    /// only its last entry is considered, since `jsonb` keeps the last value.
+    pub fn contains(&self, other: &JsoncSlice) -> bool {
+        if self.kind() == Some(ValueKind::Array) && is_scalar(other) {
+            return self
+                .array_iter()
+                .unwrap()
+                .any(|element| contains(element, *other));
+        }
+        contains(*self, *other)
+    }
+
+    /// Tests whether `other` contains this value, like the PostgreSQL `jsonb`
+    /// operator `<@`. See [`JsoncSlice::contains`].
+    pub fn is_contained_by(&self, other: &JsoncSlice) -> bool {
+        other.contains(self)
+    }
+}
+
+impl<'a> JsoncSlice<'a> {
+    /// Tests whether `key` is a key of this object, like the PostgreSQL
+    /// `jsonb` operator `?`. The string elements of an array count as its
+    /// keys, and a string counts as its own key.
+    ///
+    /// `Jsonc` has no key index, so this reads the top level of the value
+    /// once, stopping when the key is found.
+    pub fn exists(&self, key: &str) -> bool {
+        top_level_keys(self).any(|k| k == key)
+    }
+
+    /// Tests whether any of `keys` exists, like `?|`. See [`JsoncSlice::exists`].
+    pub fn exists_any(&self, keys: &[&str]) -> bool {
+        top_level_keys(self).any(|k| keys.contains(&k))
+    }
+
+    /// Tests whether all of `keys` exist, like `?&`. See [`JsoncSlice::exists`].
+    pub fn exists_all(&self, keys: &[&str]) -> bool {
+        let mut missing = keys.to_vec();
+        for k in top_level_keys(self) {
+            if missing.is_empty() {
+                break;
+            }
+            missing.retain(|key| *key != k);
+        }
+        missing.is_empty()
+    }
+
+    /// Like [`JsoncSlice::exists`] on the value at `path`, and `false` if
+    /// there is none.
+    pub fn exists_at(&self, path: &JsonPath, key: &str) -> bool {
+        self.get_json_path(path)
+            .is_some_and(|found| found.exists(key))
+    }
+
+    /// Like [`JsoncSlice::exists_any`] on the value at `path`.
+    pub fn exists_any_at(&self, path: &JsonPath, keys: &[&str]) -> bool {
+        self.get_json_path(path)
+            .is_some_and(|found| found.exists_any(keys))
+    }
+
+    /// Like [`JsoncSlice::exists_all`] on the value at `path`.
+    pub fn exists_all_at(&self, path: &JsonPath, keys: &[&str]) -> bool {
+        self.get_json_path(path)
+            .is_some_and(|found| found.exists_all(keys))
+    }
+}
+
+/// The keys of an object, the string elements of an array, or a string.
+fn top_level_keys<'a>(json: &JsoncSlice<'a>) -> impl Iterator<Item = &'a str> {
+    let keys = json.keys().into_iter().flatten();
+    let elements = json.array_iter().into_iter().flatten();
+    keys.chain(elements.filter_map(|element| element.as_str()))
+        .chain(json.as_str())
+}
+
+impl Jsonc {
+    /// See [`JsoncSlice::contains`].
+    pub fn contains(&self, other: &JsoncSlice) -> bool {
+        self.as_slice().contains(other)
+    }
+
+    /// See [`JsoncSlice::exists`].
+    pub fn exists(&self, key: &str) -> bool {
+        self.as_slice().exists(key)
+    }
+
+    /// See [`JsoncSlice::exists_any`].
+    pub fn exists_any(&self, keys: &[&str]) -> bool {
+        self.as_slice().exists_any(keys)
+    }
+
+    /// See [`JsoncSlice::exists_all`].
+    pub fn exists_all(&self, keys: &[&str]) -> bool {
+        self.as_slice().exists_all(keys)
+    }
+}
+
+fn is_scalar(json: &JsoncSlice) -> bool {
+    !matches!(
+        json.kind(),
+        None | Some(ValueKind::Array | ValueKind::Object)
+    )
+}
+
+fn contains(container: JsoncSlice, contained: JsoncSlice) -> bool {
+    match (container.kind(), contained.kind()) {
+        (Some(ValueKind::Object), Some(ValueKind::Object)) => {
+            contained.object_iter().unwrap().all(|(key, _)| {
+                let value = last_value(contained, key).unwrap();
+                last_value(container, key).is_some_and(|found| contains(found, value))
+            })
+        }
+        (Some(ValueKind::Array), Some(ValueKind::Array)) => {
+            contained.array_iter().unwrap().all(|wanted| {
+                container
+                    .array_iter()
+                    .unwrap()
+                    .any(|element| contains(element, wanted))
+            })
+        }
+        (Some(kind), Some(other_kind)) if kind == other_kind => container.semantic_eq(&contained),
+        _ => false,
+    }
+}
+
+/// The value of the last entry of `object` with `key`.
+fn last_value<'a>(object: JsoncSlice<'a>, key: &str) -> Option<JsoncSlice<'a>> {
+    object
+        .object_iter()
+        .unwrap()
+        .rev()
+        .find(|(k, _)| *k == key)
+        .map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_value;
+    use crate::path::JsonPath;
+
+    fn contains(container: &str, contained: &str) -> bool {
+        let container = parse_value(container.as_bytes());
+        let contained = parse_value(contained.as_bytes());
+        container.contains(&contained.as_slice())
+    }
+
+    #[test]
+    fn test_contains() {
+        assert!(contains(r#""foo""#, r#""foo""#));
+        assert!(contains("1", "1.0"));
+        assert!(!contains("1", r#""1""#));
+        assert!(contains("[1, 2, 3]", "[1, 3]"));
+        assert!(contains("[1, 2, 3]", "[3, 1]"));
+        assert!(contains("[1, 2, 3]", "[1, 2, 2]"));
+        assert!(contains("[]", "[]"));
+        assert!(contains("[1]", "[]"));
+        assert!(!contains("[]", "[1]"));
+        assert!(contains(
+            r#"{"product": "PostgreSQL", "version": 9.4, "jsonb": true}"#,
+            r#"{"version": 9.4}"#
+        ));
+        assert!(!contains("[1, 2, [1, 3]]", "[1, 3]"));
+        assert!(contains("[1, 2, [1, 3]]", "[[1, 3]]"));
+        assert!(!contains(r#"{"foo": {"bar": "baz"}}"#, r#"{"bar": "baz"}"#));
+        assert!(contains(r#"{"foo": {"bar": "baz"}}"#, r#"{"foo": {}}"#));
+        assert!(contains(r#"["foo", "bar"]"#, r#""foo""#));
+        assert!(!contains(r#""foo""#, r#"["foo"]"#));
+        assert!(!contains(r#"{"a": ["x"]}"#, r#"{"a": "x"}"#));
+        assert!(contains(r#"[{"a": 1, "b": 2}, 3]"#, r#"[{"a": 1}]"#));
+        assert!(!contains(r#"{"a": 1}"#, "[]"));
+        assert!(!contains(r#"{"a": 1, "a": 2}"#, r#"{"a": 1}"#));
+        assert!(contains(r#"{"a": 1, "a": 2}"#, r#"{"a": 2}"#));
+        assert!(contains(r#"{"a": 2}"#, r#"{"a": 1, "a": 2}"#));
+        assert!(!contains(r#"{"a": 1}"#, r#"{"a": 1, "a": 2}"#));
+    }
+
+    #[test]
+    fn test_exists() {
+        let json = parse_value(br#"{"a":1,"b":{"c":null},"tags":["x","y",1,["z"]],"s":"w"}"#);
+        assert!(json.exists("a"));
+        assert!(json.exists("b"));
+        assert!(!json.exists("c"));
+        assert!(json.exists_any(&["c", "b"]));
+        assert!(!json.exists_any(&["c", "d"]));
+        assert!(!json.exists_any(&[]));
+        assert!(json.exists_all(&["a", "b", "a"]));
+        assert!(!json.exists_all(&["a", "c"]));
+        assert!(json.exists_all(&[]));
+
+        let tags = JsonPath::parse("tags").unwrap();
+        assert!(json.as_slice().exists_at(&tags, "x"));
+        assert!(!json.as_slice().exists_at(&tags, "z"));
+        assert!(json.as_slice().exists_all_at(&tags, &["x", "y"]));
+        assert!(!json.as_slice().exists_any_at(&tags, &["1"]));
+        let path = JsonPath::parse("s").unwrap();
+        assert!(json.as_slice().exists_at(&path, "w"));
+        let path = JsonPath::parse("a").unwrap();
+        assert!(!json.as_slice().exists_any_at(&path, &["1"]));
+        let path = JsonPath::parse("missing").unwrap();
+        assert!(!json.as_slice().exists_all_at(&path, &[]));
+    }
+}
@@ -1,14 +1,95 @@
-use core::panic;
-
 use jsonb::util::parse_string;
 
+use crate::error::{Error, Result};
 use crate::value::{Jsonc, Node};
 
+/// Parses `buf` as one JSON value.
+///
+/// # Panics
+///
+/// Panics if `buf` is not valid JSON; see [`try_parse_value`].
 pub fn parse_value(buf: &[u8]) -> Jsonc {
+    try_parse_value(buf).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Parses `buf` as one JSON value, failing with [`Error::Syntax`] at the
+/// first byte that does not fit.
+pub fn try_parse_value(buf: &[u8]) -> Result<Jsonc> {
     let mut json = Jsonc::default();
     let mut parser = Parser::new(buf, &mut json);
-    parser.parse();
-    json
+    parser.parse()?;
+    Ok(json)
+}
+
+/// Parses the JSON value at the start of `buf`, which may be followed by
+/// anything, returning it with the number of bytes it took up: the
+/// whitespace before the value and the value itself, but not the
+/// whitespace after it.
+///
+/// A number runs as far as its digits do, so `12` followed by `34` reads as
+/// `1234`; put whitespace between values that need it.
+pub fn parse_prefix(buf: &[u8]) -> Result<(Jsonc, usize)> {
+    let mut json = Jsonc::default();
+    let mut parser = Parser::new(buf, &mut json);
+    parser.parse_json_value()?;
+    let len = parser.idx;
+    Ok((json, len))
+}
+
+/// Parses the JSON values of `buf` one after another, such as
+/// `{"a":1}{"a":2} [3]`, with or without whitespace between them; see
+/// [`parse_prefix`].
+pub fn parse_concatenated(buf: &[u8]) -> Concatenated {
+    Concatenated { buf, pos: 0 }
+}
+
+/// The iterator of [`parse_concatenated`]. After a value fails to parse
+/// there is no telling where the next one starts, so the error is the last
+/// item, and its position is an offset into the whole buffer.
+pub struct Concatenated<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Concatenated<'a> {
+    /// The number of bytes parsed so far.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+impl<'a> Iterator for Concatenated<'a> {
+    type Item = Result<Jsonc>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.buf[self.pos..];
+        let mut json = Jsonc::default();
+        let mut parser = Parser::new(rest, &mut json);
+        parser.skip_unused();
+        if parser.idx == rest.len() {
+            self.pos = self.buf.len();
+            return None;
+        }
+        let result = parser.parse_json_value();
+        let len = parser.idx;
+        match result {
+            Ok(()) => {
+                self.pos += len;
+                Some(Ok(json))
+            }
+            Err(err) => {
+                let err = match err {
+                    Error::Syntax { msg, pos } => Error::Syntax {
+                        msg,
+                        pos: self.pos + pos,
+                    },
+                    err => err,
+                };
+                self.pos = self.buf.len();
+                Some(Err(err))
+            }
+        }
+    }
 }
 
 struct Parser<'a> {
@@ -22,18 +103,26 @@ impl<'a> Parser<'a> {
         Self { buf, json, idx: 0 }
     }
 
-    fn parse(&mut self) {
-        self.parse_json_value();
+    fn parse(&mut self) -> Result<()> {
+        self.parse_json_value()?;
         self.skip_unused();
         if self.idx < self.buf.len() {
-            self.step();
-            panic!("Unexpected trailing characters")
+            return self.error("Unexpected trailing characters");
         }
+        Ok(())
+    }
+
+    /// Fails with `msg` at the current position.
+    fn error<T>(&self, msg: &str) -> Result<T> {
+        Err(Error::Syntax {
+            msg: msg.to_string(),
+            pos: self.idx,
+        })
     }
 
-    fn parse_json_value(&mut self) {
+    fn parse_json_value(&mut self) -> Result<()> {
         self.skip_unused();
-        let c = self.next();
+        let c = self.next()?;
         match c {
             b'n' => self.parse_json_null(),
             b't' => self.parse_json_true(),
@@ -42,30 +131,23 @@ impl<'a> Parser<'a> {
             b'"' => self.parse_json_string(),
             b'[' => self.parse_json_array(),
             b'{' => self.parse_json_object(),
-            _ => {
-                self.step();
-                panic!("Unexpected character")
-            }
-        };
+            _ => self.error("Unexpected character"),
+        }
     }
 
-    fn next(&mut self) -> &u8 {
+    fn next(&self) -> Result<u8> {
         match self.buf.get(self.idx) {
-            Some(c) => c,
-            None => panic!("Unexpected EOF"),
+            Some(c) => Ok(*c),
+            None => self.error("Unexpected EOF"),
         }
     }
 
-    fn must_is(&mut self, c: u8) {
-        match self.buf.get(self.idx) {
-            Some(v) => {
-                self.step();
-                if v != &c {
-                    panic!("Unexpected character")
-                }
-            }
-            None => panic!("Unexpected EOF"),
+    fn must_is(&mut self, c: u8) -> Result<()> {
+        if self.next()? != c {
+            return self.error("Unexpected character");
         }
+        self.step();
+        Ok(())
     }
 
     fn check_next(&mut self, c: u8) -> bool {
@@ -98,9 +180,9 @@ impl<'a> Parser<'a> {
         false
     }
 
-    fn step_digits(&mut self) -> usize {
+    fn step_digits(&mut self) -> Result<usize> {
         if self.idx == self.buf.len() {
-            panic!("Unexpected EOF")
+            return self.error("Unexpected EOF");
         }
         let mut len = 0;
         while self.idx < self.buf.len() {
@@ -111,7 +193,7 @@ impl<'a> Parser<'a> {
             len += 1;
             self.step();
         }
-        len
+        Ok(len)
     }
 
     #[inline]
@@ -153,31 +235,34 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_json_null(&mut self) {
+    fn parse_json_null(&mut self) -> Result<()> {
         let data = [b'n', b'u', b'l', b'l'];
         for v in data.into_iter() {
-            self.must_is(v);
+            self.must_is(v)?;
         }
         self.json.nodes.push(Node::Null);
+        Ok(())
     }
 
-    fn parse_json_true(&mut self) {
+    fn parse_json_true(&mut self) -> Result<()> {
         let data = [b't', b'r', b'u', b'e'];
         for v in data.into_iter() {
-            self.must_is(v);
+            self.must_is(v)?;
         }
         self.json.nodes.push(Node::True);
+        Ok(())
     }
 
-    fn parse_json_false(&mut self) {
+    fn parse_json_false(&mut self) -> Result<()> {
         let data = [b'f', b'a', b'l', b's', b'e'];
         for v in data.into_iter() {
-            self.must_is(v);
+            self.must_is(v)?;
         }
         self.json.nodes.push(Node::False);
+        Ok(())
     }
 
-    fn parse_json_number(&mut self) {
+    fn parse_json_number(&mut self) -> Result<()> {
         let start_idx = self.idx;
 
         let mut has_fraction = false;
@@ -191,23 +276,16 @@ impl<'a> Parser<'a> {
         if self.check_next(b'0') {
             self.step();
             if self.check_digit() {
-                self.step();
-                panic!("Invalid number value")
-            }
-        } else {
-            let len = self.step_digits();
-            if len == 0 {
-                self.step();
-                panic!("Invalid number value")
+                return self.error("Invalid number value");
             }
+        } else if self.step_digits()? == 0 {
+            return self.error("Invalid number value");
         }
         if self.check_next(b'.') {
             has_fraction = true;
             self.step();
-            let len = self.step_digits();
-            if len == 0 {
-                self.step();
-                panic!("Invalid number value")
+            if self.step_digits()? == 0 {
+                return self.error("Invalid number value");
             }
         }
         if self.check_next_either(b'E', b'e') {
@@ -216,10 +294,8 @@ impl<'a> Parser<'a> {
             if self.check_next_either(b'+', b'-') {
                 self.step();
             }
-            let len = self.step_digits();
-            if len == 0 {
-                self.step();
-                panic!("Invalid number value")
+            if self.step_digits()? == 0 {
+                return self.error("Invalid number value");
             }
         }
         let s = unsafe { std::str::from_utf8_unchecked(&self.buf[start_idx..self.idx]) };
@@ -229,12 +305,12 @@ impl<'a> Parser<'a> {
                 if let Ok(v) = s.parse::<u64>() {
                     self.json.nodes.push(Node::Number);
                     self.json.numbers.push(v as f64);
-                    return;
+                    return Ok(());
                 }
             } else if let Ok(v) = s.parse::<i64>() {
                 self.json.nodes.push(Node::Number);
                 self.json.numbers.push(v as f64);
-                return;
+                return Ok(());
             }
         }
 
@@ -242,27 +318,28 @@ impl<'a> Parser<'a> {
             Ok(v) => {
                 self.json.nodes.push(Node::Number);
                 self.json.numbers.push(v);
+                Ok(())
             }
-            Err(_) => panic!("Invalid number value"),
+            Err(_) => self.error("Invalid number value"),
         }
     }
 
-    fn parse_json_string(&mut self) {
-        self.must_is(b'"');
+    fn parse_json_string(&mut self) -> Result<()> {
+        self.must_is(b'"')?;
 
         let start_idx = self.idx;
         let mut escapes = 0;
         loop {
-            let c = self.next();
+            let c = self.next()?;
             match c {
                 b'\\' => {
                     self.step();
                     escapes += 1;
-                    let next_c = self.next();
-                    if *next_c == b'u' {
+                    let next_c = self.next()?;
+                    if next_c == b'u' {
                         self.step();
-                        let next_c = self.next();
-                        if *next_c == b'{' {
+                        let next_c = self.next()?;
+                        if next_c == b'{' {
                             self.step_by(6);
                         } else {
                             self.step_by(4);
@@ -285,70 +362,75 @@ impl<'a> Parser<'a> {
         let val = if escapes > 0 {
             let len = self.idx - 1 - start_idx - escapes;
             let mut idx = start_idx + 1;
-            parse_string(data, len, &mut idx).unwrap()
+            parse_string(data, len, &mut idx).ok()
         } else {
-            std::str::from_utf8(data).unwrap().to_string()
+            std::str::from_utf8(data).ok().map(str::to_string)
+        };
+        let Some(val) = val else {
+            return self.error("Invalid string value");
         };
         self.json.nodes.push(Node::String);
         self.json.strings.push(val);
+        Ok(())
     }
 
-    fn parse_json_array(&mut self) {
-        self.must_is(b'[');
+    fn parse_json_array(&mut self) -> Result<()> {
+        self.must_is(b'[')?;
 
         self.json.nodes.push(Node::StartArray);
         let mut first = true;
         loop {
             self.skip_unused();
-            let c = self.next();
-            if *c == b']' {
+            let c = self.next()?;
+            if c == b']' {
                 self.step();
                 break;
             }
             if !first {
-                if *c != b',' {
-                    panic!("Unexpected character")
+                if c != b',' {
+                    return self.error("Unexpected character");
                 }
                 self.step();
             }
             first = false;
-            self.parse_json_value();
+            self.parse_json_value()?;
         }
         self.json.nodes.push(Node::EndArray);
+        Ok(())
     }
 
-    fn parse_json_object(&mut self) {
-        self.must_is(b'{');
+    fn parse_json_object(&mut self) -> Result<()> {
+        self.must_is(b'{')?;
 
         let mut first = true;
         self.json.nodes.push(Node::StartObject);
         loop {
             self.skip_unused();
-            let c = self.next();
-            if *c == b'}' {
+            let c = self.next()?;
+            if c == b'}' {
                 self.step();
                 break;
             }
             if !first {
-                if *c != b',' {
-                    panic!("Unexpected character")
+                if c != b',' {
+                    return self.error("Unexpected character");
                 }
                 self.step();
             }
             first = false;
-            self.parse_json_value();
+            self.parse_json_value()?;
             if !matches!(self.json.nodes.pop(), Some(Node::String)) {
-                panic!("Expected string key")
+                return self.error("Expected string key");
             }
             self.json.nodes.push(Node::Key);
             self.skip_unused();
-            let c = self.next();
-            if *c != b':' {
-                panic!("Unexpected character")
+            if self.next()? != b':' {
+                return self.error("Unexpected character");
             }
             self.step();
-            self.parse_json_value();
+            self.parse_json_value()?;
         }
         self.json.nodes.push(Node::EndObject);
+        Ok(())
     }
 }
@@ -0,0 +1,314 @@
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use jsonb::functions::escape_scalar_string;
+
+use crate::error::{Error, Result};
+use crate::jsonpath::Parser;
+use crate::slice::JsoncSlice;
+
+/// One step from a container to one of its children.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PathItem<'a> {
+    /// The value of an object entry.
+    Key(&'a str),
+    /// An element of an array.
+    Index(usize),
+}
+
+/// The location of a value in a document, displayed as an RFC 9535
+/// normalized path such as `$['statuses'][0]['id']`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct NormalizedPath<'a>(Vec<PathItem<'a>>);
+
+impl<'a> NormalizedPath<'a> {
+    pub fn items(&self) -> &[PathItem<'a>] {
+        &self.0
+    }
+}
+
+impl<'a> From<Vec<PathItem<'a>>> for NormalizedPath<'a> {
+    fn from(items: Vec<PathItem<'a>>) -> Self {
+        Self(items)
+    }
+}
+
+impl fmt::Display for NormalizedPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('$')?;
+        for item in &self.0 {
+            match item {
+                PathItem::Key(key) => {
+                    f.write_str("['")?;
+                    for c in key.chars() {
+                        match c {
+                            '\u{8}' => f.write_str("\\b")?,
+                            '\u{c}' => f.write_str("\\f")?,
+                            '\n' => f.write_str("\\n")?,
+                            '\r' => f.write_str("\\r")?,
+                            '\t' => f.write_str("\\t")?,
+                            '\'' => f.write_str("\\'")?,
+                            '\\' => f.write_str("\\\\")?,
+                            '\u{0}'..='\u{1f}' => write!(f, "\\u{:04x}", c as u32)?,
+                            _ => f.write_char(c)?,
+                        }
+                    }
+                    f.write_str("']")?;
+                }
+                PathItem::Index(idx) => write!(f, "[{}]", idx)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One step of a [`JsonPath`].
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum PathSegment {
+    /// The value of an object entry.
+    Key(String),
+    /// An element of an array, counting from the start.
+    Index(usize),
+    /// An element of an array, counting from the end: `IndexFromEnd(1)` is
+    /// the last element.
+    IndexFromEnd(usize),
+}
+
+/// A path to a value, parsed from a dotted syntax such as
+/// `a.b[0]["weird.key"]`.
+///
+/// A bare key runs up to the next `.` or `[`, so `areaNames.205705994`
+/// names two keys. Keys that contain `.`, `[` or `]` are written as quoted
+/// strings in brackets, with JSON escapes. Array indexes are only written in
+/// brackets, and `[-1]` is the last element. A path may start with `$.` or
+/// `.`, and the empty path and `$` both name the whole value.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Hash)]
+pub struct JsonPath(Vec<PathSegment>);
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self> {
+        let mut parser = Parser::new(path);
+        let mut segments = Vec::new();
+        if parser.eat('$') && !matches!(parser.peek(), None | Some('.' | '[')) {
+            return parser.error("expected `.` or `[`");
+        }
+        if !matches!(parser.peek(), None | Some('.' | '[')) {
+            segments.push(PathSegment::Key(parse_bare_key(&mut parser, false)?));
+        }
+        while parser.peek().is_some() {
+            match parse_segment(&mut parser, false)? {
+                Some(segment) => segments.push(segment),
+                None => return parser.error("expected `.` or `[`"),
+            }
+        }
+        Ok(Self(segments))
+    }
+
+    /// Parses a path that starts with `$` and is part of a larger expression,
+    /// stopping where the path ends. Bare keys here also end at whitespace
+    /// and at any of `(),<>=!&|`.
+    pub(crate) fn parse_embedded(parser: &mut Parser) -> Result<Self> {
+        parser.expect('$')?;
+        let mut segments = Vec::new();
+        while let Some(segment) = parse_segment(parser, true)? {
+            segments.push(segment);
+        }
+        Ok(Self(segments))
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+}
+
+/// Parses a `.key` or bracketed segment, if one is next.
+fn parse_segment(parser: &mut Parser, embedded: bool) -> Result<Option<PathSegment>> {
+    match parser.peek() {
+        Some('.') => {
+            parser.bump();
+            Ok(Some(PathSegment::Key(parse_bare_key(parser, embedded)?)))
+        }
+        Some('[') => {
+            parser.bump();
+            let segment = parse_bracketed(parser)?;
+            parser.expect(']')?;
+            Ok(Some(segment))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn parse_bare_key(parser: &mut Parser, embedded: bool) -> Result<String> {
+    let mut key = String::new();
+    while let Some(c) = parser.peek() {
+        match c {
+            '.' | '[' => break,
+            '(' | ')' | ',' | '<' | '>' | '=' | '!' | '&' | '|' if embedded => break,
+            _ if embedded && c.is_whitespace() => break,
+            ']' | '"' | '\'' => return parser.error("unexpected character in key"),
+            _ => key.push(c),
+        }
+        parser.bump();
+    }
+    if key.is_empty() {
+        return parser.error("expected a key");
+    }
+    Ok(key)
+}
+
+fn parse_bracketed(parser: &mut Parser) -> Result<PathSegment> {
+    if matches!(parser.peek(), Some('"' | '\'')) {
+        return Ok(PathSegment::Key(parser.parse_string()?));
+    }
+    match parser.parse_opt_int()? {
+        Some(idx) if idx < 0 => Ok(PathSegment::IndexFromEnd(idx.unsigned_abs() as usize)),
+        Some(idx) => Ok(PathSegment::Index(idx as usize)),
+        None => parser.error("expected a quoted key or an index"),
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = Error;
+
+    fn from_str(path: &str) -> Result<Self> {
+        Self::parse(path)
+    }
+}
+
+impl From<Vec<PathSegment>> for JsonPath {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self(segments)
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_char('$');
+        }
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if is_bare_key(key) => {
+                    if i > 0 {
+                        f.write_char('.')?;
+                    }
+                    f.write_str(key)?;
+                }
+                PathSegment::Key(key) => {
+                    let mut quoted = String::with_capacity(key.len() + 2);
+                    escape_scalar_string(key.as_bytes(), 0, key.len(), &mut quoted);
+                    write!(f, "[{}]", quoted)?;
+                }
+                PathSegment::Index(idx) => write!(f, "[{}]", idx)?,
+                PathSegment::IndexFromEnd(idx) => write!(f, "[-{}]", idx)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with('$')
+        && !key
+            .chars()
+            .any(|c| matches!(c, '.' | '[' | ']' | '"' | '\'') || c.is_control())
+}
+
+/// Types that [`Jsonc::get`](crate::value::Jsonc::get) and [`JsoncSlice::get`]
+/// accept as a path: strings in the [`JsonPath`] syntax, parsed paths and
+/// lists of segments.
+pub trait IntoJsonPath {
+    fn into_json_path(self) -> Result<JsonPath>;
+}
+
+impl IntoJsonPath for JsonPath {
+    fn into_json_path(self) -> Result<JsonPath> {
+        Ok(self)
+    }
+}
+
+impl IntoJsonPath for &JsonPath {
+    fn into_json_path(self) -> Result<JsonPath> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoJsonPath for &str {
+    fn into_json_path(self) -> Result<JsonPath> {
+        JsonPath::parse(self)
+    }
+}
+
+impl IntoJsonPath for &String {
+    fn into_json_path(self) -> Result<JsonPath> {
+        JsonPath::parse(self)
+    }
+}
+
+impl IntoJsonPath for &[PathSegment] {
+    fn into_json_path(self) -> Result<JsonPath> {
+        Ok(JsonPath(self.to_vec()))
+    }
+}
+
+impl<'a> JsoncSlice<'a> {
+    /// Follows `path` from this value, returning `None` if any step is missing.
+    pub fn get_json_path(&self, path: &JsonPath) -> Option<JsoncSlice<'a>> {
+        let mut json_slice = *self;
+        for segment in path.segments() {
+            json_slice = match segment {
+                PathSegment::Key(key) => json_slice.get_by_path(key)?,
+                PathSegment::Index(idx) => json_slice.get_by_idx(*idx)?,
+                PathSegment::IndexFromEnd(idx) => {
+                    json_slice.array_iter()?.nth_back(idx.checked_sub(1)?)?
+                }
+            };
+        }
+        Some(json_slice)
+    }
+
+    /// Parses `path` and follows it like [`JsoncSlice::get_json_path`],
+    /// failing only if the path is malformed.
+    pub fn get<P: IntoJsonPath>(&self, path: P) -> Result<Option<JsoncSlice<'a>>> {
+        Ok(self.get_json_path(&path.into_json_path()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_path_parse() {
+        let path = JsonPath::parse(r#"a.b[0]["weird.key"][-1].0"#).unwrap();
+        assert_eq!(
+            path.segments(),
+            &[
+                PathSegment::Key("a".to_string()),
+                PathSegment::Key("b".to_string()),
+                PathSegment::Index(0),
+                PathSegment::Key("weird.key".to_string()),
+                PathSegment::IndexFromEnd(1),
+                PathSegment::Key("0".to_string()),
+            ]
+        );
+        assert_eq!(path.to_string(), r#"a.b[0]["weird.key"][-1].0"#);
+        assert_eq!(JsonPath::parse("$.a['b']").unwrap().to_string(), "a.b");
+        assert_eq!(JsonPath::parse(".a[1]").unwrap().to_string(), "a[1]");
+        assert!(JsonPath::parse("").unwrap().segments().is_empty());
+        assert!(JsonPath::parse("$").unwrap().segments().is_empty());
+        let path = JsonPath::from(vec![PathSegment::Key("$ref".to_string())]);
+        assert_eq!(JsonPath::parse(&path.to_string()).unwrap(), path);
+
+        for path in [
+            "a..b", "a.", "a.[0]", "a[", "a[x]", "a[01]", "a[-0]", "a]", "$a", "a['b'",
+        ] {
+            assert!(
+                matches!(JsonPath::parse(path), Err(Error::Syntax { .. })),
+                "{}",
+                path
+            );
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::slice::JsoncSlice;
+use crate::value::{Jsonc, Node};
+
+impl<'a> JsoncSlice<'a> {
+    /// Looks up a value by an RFC 6901 JSON Pointer such as
+    /// `/search_metadata/max_id_str`. The empty pointer refers to the whole
+    /// value, and `~1` and `~0` in a reference token stand for `/` and `~`.
+    ///
+    /// Returns `None` if the pointer is malformed or nothing is found at it.
+    pub fn pointer(&self, pointer: &str) -> Option<JsoncSlice<'a>> {
+        if pointer.is_empty() {
+            return Some(*self);
+        }
+        let mut json_slice = *self;
+        for token in pointer.strip_prefix('/')?.split('/') {
+            let token = unescape_token(token)?;
+            json_slice = match json_slice.nodes.first()? {
+                Node::StartObject => json_slice.get_by_path(&token)?,
+                Node::StartArray => json_slice.get_by_idx(parse_array_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(json_slice)
+    }
+}
+
+fn unescape_token(token: &str) -> Option<Cow<str>> {
+    if !token.contains('~') {
+        return Some(Cow::Borrowed(token));
+    }
+    let mut unescaped = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c == '~' {
+            match chars.next()? {
+                '0' => unescaped.push('~'),
+                '1' => unescaped.push('/'),
+                _ => return None,
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Some(Cow::Owned(unescaped))
+}
+
+/// Parses an array index token, which is `0` or a number without leading
+/// zeros. The `-` token names the element after the last one, which never
+/// exists for a lookup.
+fn parse_array_index(token: &str) -> Option<usize> {
+    if token.is_empty()
+        || !token.bytes().all(|b| b.is_ascii_digit())
+        || (token.len() > 1 && token.starts_with('0'))
+    {
+        return None;
+    }
+    token.parse().ok()
+}
+
+impl Jsonc {
+    /// See [`JsoncSlice::pointer`].
+    pub fn pointer(&self, pointer: &str) -> Option<JsoncSlice> {
+        self.as_slice().pointer(pointer)
+    }
+
+    /// Looks up a value by JSON Pointer like [`Jsonc::pointer`], returning a
+    /// handle that can edit it in place.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<JsoncMut> {
+        let root = self.as_slice();
+        let found = root.pointer(pointer)?;
+        let nodes = range_in(root.nodes, found.nodes);
+        let strings = range_in(root.strings, found.strings);
+        let numbers = range_in(root.numbers, found.numbers);
+        Some(JsoncMut {
+            jsonc: self,
+            nodes,
+            strings,
+            numbers,
+        })
+    }
+}
+
+/// Locates `part`, which must be a subslice of `whole`, as a range of indices.
+fn range_in<T>(whole: &[T], part: &[T]) -> Range<usize> {
+    let start = (part.as_ptr() as usize - whole.as_ptr() as usize) / std::mem::size_of::<T>();
+    start..start + part.len()
+}
+
+/// A value inside a `Jsonc` that can be edited, created by [`Jsonc::pointer_mut`].
+#[derive(Debug)]
+pub struct JsoncMut<'a> {
+    jsonc: &'a mut Jsonc,
+    nodes: Range<usize>,
+    strings: Range<usize>,
+    numbers: Range<usize>,
+}
+
+impl JsoncMut<'_> {
+    pub fn as_slice(&self) -> JsoncSlice {
+        JsoncSlice {
+            nodes: &self.jsonc.nodes[self.nodes.clone()],
+            strings: &self.jsonc.strings[self.strings.clone()],
+            numbers: &self.jsonc.numbers[self.numbers.clone()],
+        }
+    }
+
+    pub fn as_str_mut(&mut self) -> Option<&mut String> {
+        match self.jsonc.nodes[self.nodes.clone()].first() {
+            Some(Node::String) => Some(&mut self.jsonc.strings[self.strings.start]),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64_mut(&mut self) -> Option<&mut f64> {
+        match self.jsonc.nodes[self.nodes.clone()].first() {
+            Some(Node::Number) => Some(&mut self.jsonc.numbers[self.numbers.start]),
+            _ => None,
+        }
+    }
+
+    /// Replaces the value with `value`, which may have any type or size.
+    pub fn replace(self, value: JsoncSlice) {
+        self.jsonc
+            .nodes
+            .splice(self.nodes, value.nodes.iter().copied());
+        self.jsonc
+            .strings
+            .splice(self.strings, value.strings.iter().cloned());
+        self.jsonc
+            .numbers
+            .splice(self.numbers, value.numbers.iter().copied());
+    }
+}
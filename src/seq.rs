@@ -0,0 +1,286 @@
+use std::io;
+
+use crate::decoder::{encode_to_with, DecodeOptions};
+use crate::error::{Error, Result};
+use crate::parser::try_parse_value;
+use crate::slice::{JsoncSlice, ValueKind};
+use crate::value::Jsonc;
+
+/// The record separator that starts each text of an RFC 7464 sequence.
+const RS: u8 = 0x1E;
+
+/// How [`SeqWriter`] and [`SeqReader`] frame their records.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Framing {
+    /// Newline-delimited JSON: each record is followed by `\n`.
+    Ndjson,
+    /// RFC 7464 JSON Text Sequences: each record is `RS` (`0x1E`), the
+    /// JSON text, then `\n`.
+    JsonSeq,
+}
+
+/// Writes records to an [`io::Write`] one at a time.
+///
+/// A record is written as compact JSON, which escapes newlines in strings,
+/// so no raw newline appears inside it. Each record is encoded in full
+/// before any of it is written, so a record that fails to encode, such as
+/// one holding a NaN, leaves nothing behind.
+pub struct SeqWriter<W: io::Write> {
+    writer: W,
+    framing: Framing,
+    options: DecodeOptions,
+    record: Vec<u8>,
+}
+
+impl<W: io::Write> SeqWriter<W> {
+    pub fn new(writer: W, framing: Framing) -> Self {
+        Self::with_options(writer, framing, DecodeOptions::default())
+    }
+
+    /// Like [`SeqWriter::new`], encoding records as `options` say.
+    pub fn with_options(writer: W, framing: Framing, options: DecodeOptions) -> Self {
+        Self {
+            writer,
+            framing,
+            options,
+            record: Vec::new(),
+        }
+    }
+
+    /// Writes `json` as one record.
+    pub fn write<'a>(&mut self, json: impl Into<JsoncSlice<'a>>) -> Result<()> {
+        self.record.clear();
+        if self.framing == Framing::JsonSeq {
+            self.record.push(RS);
+        }
+        encode_to_with(json.into(), &mut self.record, &self.options)?;
+        self.record.push(b'\n');
+        self.writer.write_all(&self.record)?;
+        Ok(())
+    }
+
+    /// Writes each of `records`, stopping at the first failure.
+    pub fn write_all<'a, I>(&mut self, records: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Into<JsoncSlice<'a>>,
+    {
+        records.into_iter().try_for_each(|json| self.write(json))
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Writes `records` to `writer` as newline-delimited JSON.
+pub fn write_ndjson<'a, W, I>(writer: W, records: I) -> Result<()>
+where
+    W: io::Write,
+    I: IntoIterator,
+    I::Item: Into<JsoncSlice<'a>>,
+{
+    let mut writer = SeqWriter::new(writer, Framing::Ndjson);
+    writer.write_all(records)?;
+    writer.flush()
+}
+
+/// Writes `records` to `writer` as an RFC 7464 JSON Text Sequence.
+pub fn write_json_seq<'a, W, I>(writer: W, records: I) -> Result<()>
+where
+    W: io::Write,
+    I: IntoIterator,
+    I::Item: Into<JsoncSlice<'a>>,
+{
+    let mut writer = SeqWriter::new(writer, Framing::JsonSeq);
+    writer.write_all(records)?;
+    writer.flush()
+}
+
+/// An iterator over the records of a buffer, yielding an error for each
+/// record that does not parse and carrying on with the next one. Blank
+/// records are skipped, and the positions in [`Error::Syntax`] are offsets
+/// into the whole buffer.
+///
+/// With [`Framing::JsonSeq`], a record runs from one `RS` to the next, as
+/// RFC 7464 asks, so a truncated record costs only itself:
+///
+/// - bytes before the first `RS` fail with "Missing record separator",
+/// - a record that does not parse, such as `{"a":` cut short by the next
+///   `RS`, fails where the parser stops,
+/// - a number, `true`, `false` or `null` with no whitespace after it fails
+///   with "Truncated record", since `12` may be what is left of `1234`.
+pub struct SeqReader<'a> {
+    buf: &'a [u8],
+    framing: Framing,
+    pos: usize,
+}
+
+impl<'a> SeqReader<'a> {
+    pub fn new(buf: &'a [u8], framing: Framing) -> Self {
+        Self {
+            buf,
+            framing,
+            pos: 0,
+        }
+    }
+
+    /// Finds the record at `self.pos`, returning where it starts and
+    /// whether it follows a separator, and moves past it.
+    fn next_record(&mut self) -> (usize, bool) {
+        let mut start = self.pos;
+        let separator = match self.framing {
+            Framing::Ndjson => b'\n',
+            Framing::JsonSeq => RS,
+        };
+        let delimited = match self.framing {
+            Framing::Ndjson => true,
+            Framing::JsonSeq if self.buf[start] == RS => {
+                start += 1;
+                true
+            }
+            Framing::JsonSeq => false,
+        };
+        self.pos = match self.buf[start..].iter().position(|c| *c == separator) {
+            Some(len) if self.framing == Framing::Ndjson => start + len + 1,
+            Some(len) => start + len,
+            None => self.buf.len(),
+        };
+        (start, delimited)
+    }
+
+    fn parse_record(&self, start: usize, record: &[u8]) -> Result<Jsonc> {
+        let json = try_parse_value(record).map_err(|err| match err {
+            Error::Syntax { msg, pos } => Error::Syntax {
+                msg,
+                pos: start + pos,
+            },
+            err => err,
+        })?;
+        if self.framing == Framing::JsonSeq
+            && matches!(
+                json.as_slice().kind(),
+                Some(ValueKind::Null | ValueKind::Bool | ValueKind::Number)
+            )
+            && !record.last().is_some_and(u8::is_ascii_whitespace)
+        {
+            return Err(Error::Syntax {
+                msg: "Truncated record".to_string(),
+                pos: start + record.len(),
+            });
+        }
+        Ok(json)
+    }
+}
+
+impl<'a> Iterator for SeqReader<'a> {
+    type Item = Result<Jsonc>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.buf.len() {
+            let (start, delimited) = self.next_record();
+            let record = &self.buf[start..self.pos];
+            if record.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            if !delimited {
+                return Some(Err(Error::Syntax {
+                    msg: "Missing record separator".to_string(),
+                    pos: start,
+                }));
+            }
+            return Some(self.parse_record(start, record));
+        }
+        None
+    }
+}
+
+/// Reads newline-delimited JSON; see [`SeqReader`].
+pub fn read_ndjson(buf: &[u8]) -> SeqReader {
+    SeqReader::new(buf, Framing::Ndjson)
+}
+
+/// Reads an RFC 7464 JSON Text Sequence; see [`SeqReader`].
+pub fn read_json_seq(buf: &[u8]) -> SeqReader {
+    SeqReader::new(buf, Framing::JsonSeq)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode;
+    use crate::parser::parse_value;
+    use crate::value::Node;
+
+    fn decoded(records: SeqReader) -> Vec<std::result::Result<String, usize>> {
+        records
+            .map(|record| match record {
+                Ok(json) => Ok(decode(&json)),
+                Err(Error::Syntax { pos, .. }) => Err(pos),
+                Err(err) => panic!("{}", err),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_write_seq() {
+        let first = parse_value(b"{\"a\": \"x\\ny\", \"b\": [1, 2]}");
+        let second = parse_value(b"\"line\\r\\nbreak\"");
+
+        let mut bytes = Vec::new();
+        write_ndjson(&mut bytes, [&first, &second]).unwrap();
+        assert_eq!(
+            bytes,
+            b"{\"a\":\"x\\ny\",\"b\":[1,2]}\n\"line\\r\\nbreak\"\n"
+        );
+        let mut lines = bytes.split(|c| *c == b'\n');
+        assert_eq!(parse_value(lines.next().unwrap()), first);
+        assert_eq!(parse_value(lines.next().unwrap()), second);
+
+        let mut bytes = Vec::new();
+        write_json_seq(&mut bytes, [first.as_slice(), second.as_slice()]).unwrap();
+        assert_eq!(
+            bytes,
+            b"\x1E{\"a\":\"x\\ny\",\"b\":[1,2]}\n\x1E\"line\\r\\nbreak\"\n"
+        );
+        let records: Vec<_> = read_json_seq(&bytes).map(Result::unwrap).collect();
+        assert_eq!(records, [first.clone(), second]);
+
+        let nan = Jsonc::new_with_values(vec![Node::Number], vec![], vec![f64::NAN]);
+        let mut writer = SeqWriter::new(Vec::new(), Framing::Ndjson);
+        writer.write(&first).unwrap();
+        assert!(matches!(writer.write(&nan), Err(Error::NonFiniteNumber(_))));
+        assert_eq!(writer.into_inner(), b"{\"a\":\"x\\ny\",\"b\":[1,2]}\n");
+    }
+
+    #[test]
+    fn test_read_seq() {
+        let input = b"junk\x1E{\"a\":1}\n\x1E{\"b\":\x1E[true]\n\x1E\x1E\n\x1E123\x1E4\n\x1Enull";
+        assert_eq!(
+            decoded(read_json_seq(input)),
+            [
+                Err(0),
+                Ok(r#"{"a":1}"#.to_string()),
+                Err(19),
+                Ok("[true]".to_string()),
+                Err(34),
+                Ok("4".to_string()),
+                Err(42),
+            ]
+        );
+
+        let input = b"{\"a\":1}\r\n\n  \n[1,\n\"x\"";
+        assert_eq!(
+            decoded(read_ndjson(input)),
+            [
+                Ok(r#"{"a":1}"#.to_string()),
+                Err(17),
+                Ok(r#""x""#.to_string())
+            ]
+        );
+    }
+}
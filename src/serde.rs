@@ -1,6 +1,11 @@
+use std::convert::Infallible;
+
 use serde_json::{Number, Value};
 
+use crate::path::PathItem;
+use crate::slice::JsoncSlice;
 use crate::value::{Jsonc, Node};
+use crate::visitor::{walk, Visitor};
 
 impl From<Value> for Jsonc {
     fn from(value: Value) -> Self {
@@ -47,73 +52,110 @@ impl From<Value> for Jsonc {
     }
 }
 
-/// Returns value, the number of nodes consumed, strings consumed, and numbers consumed
-fn jsonc_list_to_serde_value(
-    nodes: &[Node],
-    strings: &[String],
-    numbers: &[f64],
-) -> (Value, usize, usize, usize) {
-    match nodes[0] {
-        Node::Null => (Value::Null, 1, 0, 0),
-        Node::True => (Value::Bool(true), 1, 0, 0),
-        Node::False => (Value::Bool(false), 1, 0, 0),
-        Node::Number => (
-            Value::Number(Number::from_f64(numbers[0]).unwrap()),
-            1,
-            0,
-            1,
-        ),
-        Node::String => (Value::String(strings[0].clone()), 1, 1, 0),
-        Node::StartArray => {
-            let mut arr = Vec::new();
-            let mut node_idx = 1;
-            let mut string_idx = 0;
-            let mut number_idx = 0;
-            while nodes[node_idx] != Node::EndArray {
-                let (value, i, j, k) = jsonc_list_to_serde_value(
-                    &nodes[node_idx..],
-                    &strings[string_idx..],
-                    &numbers[number_idx..],
-                );
-                node_idx += i;
-                string_idx += j;
-                number_idx += k;
-                arr.push(value);
+/// Rebuilds a `serde_json::Value` from the events of a walk.
+#[derive(Default)]
+struct ValueBuilder {
+    /// The open containers, innermost last.
+    containers: Vec<Value>,
+    /// The keys of the entries being built in the open objects, innermost last.
+    keys: Vec<String>,
+    root: Option<Value>,
+}
+
+impl ValueBuilder {
+    fn push(&mut self, value: Value) {
+        match self.containers.last_mut() {
+            Some(Value::Array(arr)) => arr.push(value),
+            Some(Value::Object(obj)) => {
+                obj.insert(self.keys.pop().unwrap(), value);
             }
-            (Value::Array(arr), node_idx + 1, string_idx, number_idx)
+            _ => self.root = Some(value),
         }
-        Node::StartObject => {
-            let mut obj = serde_json::Map::new();
-            let mut node_idx = 1;
-            let mut string_idx = 0;
-            let mut number_idx = 0;
-
-            while nodes[node_idx] != Node::EndObject {
-                let key = strings[string_idx].clone();
-                string_idx += 1;
-                node_idx += 1;
-                let (value, i, j, k) = jsonc_list_to_serde_value(
-                    &nodes[node_idx..],
-                    &strings[string_idx..],
-                    &numbers[number_idx..],
-                );
-                node_idx += i;
-                string_idx += j;
-                number_idx += k;
-                obj.insert(key, value);
-            }
+    }
+}
 
-            (Value::Object(obj), node_idx + 1, string_idx, number_idx)
-        }
-        _ => panic!("Invalid node value"),
+impl<'a> Visitor<'a> for ValueBuilder {
+    type Error = Infallible;
+
+    fn start_object(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<(), Infallible> {
+        self.containers.push(Value::Object(serde_json::Map::new()));
+        Ok(())
+    }
+
+    fn end_object(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<(), Infallible> {
+        let obj = self.containers.pop().unwrap();
+        self.push(obj);
+        Ok(())
+    }
+
+    fn start_array(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<(), Infallible> {
+        self.containers.push(Value::Array(Vec::new()));
+        Ok(())
+    }
+
+    fn end_array(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<(), Infallible> {
+        let arr = self.containers.pop().unwrap();
+        self.push(arr);
+        Ok(())
+    }
+
+    fn key(
+        &mut self,
+        key: &'a str,
+        _path: &[PathItem<'a>],
+        _depth: usize,
+    ) -> Result<(), Infallible> {
+        self.keys.push(key.to_string());
+        Ok(())
+    }
+
+    fn string(
+        &mut self,
+        value: &'a str,
+        _path: &[PathItem<'a>],
+        _depth: usize,
+    ) -> Result<(), Infallible> {
+        self.push(Value::String(value.to_string()));
+        Ok(())
+    }
+
+    fn number(
+        &mut self,
+        value: f64,
+        _path: &[PathItem<'a>],
+        _depth: usize,
+    ) -> Result<(), Infallible> {
+        self.push(Value::Number(Number::from_f64(value).unwrap()));
+        Ok(())
+    }
+
+    fn bool(
+        &mut self,
+        value: bool,
+        _path: &[PathItem<'a>],
+        _depth: usize,
+    ) -> Result<(), Infallible> {
+        self.push(Value::Bool(value));
+        Ok(())
+    }
+
+    fn null(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<(), Infallible> {
+        self.push(Value::Null);
+        Ok(())
+    }
+}
+
+impl From<JsoncSlice<'_>> for Value {
+    fn from(json: JsoncSlice) -> Self {
+        let mut builder = ValueBuilder::default();
+        walk(json, &mut builder).unwrap_or_else(|never| match never {});
+        builder.root.unwrap_or_default()
     }
 }
 
 impl From<&Jsonc> for Value {
     fn from(jsonc: &Jsonc) -> Self {
-        let (value, _, _, _) =
-            jsonc_list_to_serde_value(&jsonc.nodes, &jsonc.strings, &jsonc.numbers);
-        value
+        jsonc.as_slice().into()
     }
 }
 
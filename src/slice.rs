@@ -1,6 +1,20 @@
+use std::iter::FusedIterator;
+
+use crate::error::Result;
 use crate::value::{Jsonc, Node};
 
-#[derive(Debug, PartialEq)]
+/// The JSON type of the value a `JsoncSlice` holds.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ValueKind {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+#[derive(Clone, Copy)]
 pub struct JsoncSlice<'a> {
     pub nodes: &'a [Node],
     pub strings: &'a [String],
@@ -15,152 +29,336 @@ impl<'a> JsoncSlice<'a> {
             numbers: &jsonc.numbers,
         }
     }
+
+    /// Borrows columns loaded from storage, with the node column given as `u8` tags.
+    pub fn from_node_bytes(
+        nodes: &'a [u8],
+        strings: &'a [String],
+        numbers: &'a [f64],
+    ) -> Result<Self> {
+        Ok(Self {
+            nodes: Node::from_bytes(nodes)?,
+            strings,
+            numbers,
+        })
+    }
+
+    pub fn node_bytes(&self) -> &'a [u8] {
+        Node::as_bytes(self.nodes)
+    }
 }
 
 impl<'a> JsoncSlice<'a> {
-    pub fn get_by_path(&self, path: &str) -> Option<JsoncSlice<'a>> {
-        if self.nodes.is_empty() || self.nodes[0] != Node::StartObject {
-            return None;
+    /// Returns the type of the value, or `None` for an empty slice.
+    pub fn kind(&self) -> Option<ValueKind> {
+        let kind = match self.nodes.first()? {
+            Node::Null => ValueKind::Null,
+            Node::True | Node::False => ValueKind::Bool,
+            Node::Number => ValueKind::Number,
+            Node::String => ValueKind::String,
+            Node::StartArray => ValueKind::Array,
+            Node::StartObject => ValueKind::Object,
+            Node::EndArray | Node::EndObject | Node::Key => return None,
+        };
+        Some(kind)
+    }
+
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self.nodes.first()? {
+            Node::String => self.strings.first().map(String::as_str),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.nodes.first()? {
+            Node::Number => self.numbers.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as an `i64` if it is integral and in range.
+    pub fn as_i64(&self) -> Option<i64> {
+        let n = self.as_f64()?;
+        // `i64::MAX as f64` rounds up to 2^63, which is itself out of range.
+        if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 {
+            Some(n as i64)
+        } else {
+            None
         }
-        let mut str_idx = 0;
-        let mut num_idx = 0;
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.nodes.first()? {
+            Node::True => Some(true),
+            Node::False => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self.nodes.first(), Some(Node::Null))
+    }
+
+    /// Returns the number of elements of an array or entries of an object,
+    /// or `None` for any other value.
+    pub fn len(&self) -> Option<usize> {
+        match self.nodes.first()? {
+            Node::StartArray => Some(self.array_iter()?.count()),
+            Node::StartObject => Some(self.object_iter()?.count()),
+            _ => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> Option<bool> {
+        match self.nodes.first()? {
+            Node::StartArray | Node::StartObject => Some(self.nodes.len() == 2),
+            _ => None,
+        }
+    }
+
+    /// Iterates over the elements of an array, or returns `None` if the value
+    /// is not an array.
+    pub fn array_iter(&self) -> Option<ArrayIter<'a>> {
+        match self.nodes.first()? {
+            Node::StartArray => Some(ArrayIter {
+                children: Children::new(*self),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Iterates over the key-value pairs of an object in document order, or
+    /// returns `None` if the value is not an object.
+    pub fn object_iter(&self) -> Option<ObjectIter<'a>> {
+        match self.nodes.first()? {
+            Node::StartObject => Some(ObjectIter {
+                children: Children::new(*self),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn keys(&self) -> Option<Keys<'a>> {
+        Some(Keys {
+            entries: self.object_iter()?,
+        })
+    }
+
+    pub fn get_by_path(&self, path: &str) -> Option<JsoncSlice<'a>> {
+        self.object_iter()?
+            .find(|(key, _)| *key == path)
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_by_idx(&self, idx: usize) -> Option<JsoncSlice<'a>> {
+        self.array_iter()?.nth(idx)
+    }
+
+    /// Returns the position just past the value starting at `start`.
+    fn value_end(&self, start: Cursor) -> Cursor {
+        let mut end = start;
         let mut nest = 0;
-        for (node_idx, node) in self.nodes.iter().enumerate() {
-            if nest == -1 {
-                return None;
-            }
-            if node_idx == 0 {
-                continue;
-            }
-            match node {
-                Node::Key => {
-                    if nest == 0 {
-                        let key = self.strings.get(str_idx).unwrap();
-                        if key == path {
-                            return Some(self.strip_slice(node_idx + 1, num_idx, str_idx + 1));
-                        }
-                    }
-                    str_idx += 1;
-                }
-                Node::String => {
-                    str_idx += 1;
-                }
-                Node::Number => {
-                    num_idx += 1;
-                }
+        loop {
+            match self.nodes[end.node] {
                 Node::StartArray | Node::StartObject => {
                     nest += 1;
                 }
                 Node::EndArray | Node::EndObject => {
                     nest -= 1;
                 }
+                Node::String | Node::Key => {
+                    end.string += 1;
+                }
+                Node::Number => {
+                    end.number += 1;
+                }
                 _ => {}
             }
+            end.node += 1;
+            if nest == 0 {
+                return end;
+            }
         }
-        return None;
     }
 
-    pub fn get_by_idx(&self, idx: usize) -> Option<JsoncSlice<'a>> {
-        if self.nodes.is_empty() || self.nodes[0] != Node::StartArray {
-            return None;
-        }
-        let mut count = 0;
-        let mut str_idx = 0;
-        let mut num_idx = 0;
+    /// Returns the start of the value ending just before `end`.
+    fn value_start(&self, end: Cursor) -> Cursor {
+        let mut start = end;
         let mut nest = 0;
-        for (node_idx, node) in self.nodes.iter().enumerate() {
-            if nest == -1 {
-                return None;
-            }
-            if node_idx == 0 {
-                continue;
-            }
-            if nest == 0 && count == idx {
-                return Some(self.strip_slice(node_idx, num_idx, str_idx));
-            }
-            match node {
-                Node::String => {
-                    count += 1;
-                    str_idx += 1;
-                }
-                Node::Number => {
-                    count += 1;
-                    num_idx += 1;
-                }
+        loop {
+            start.node -= 1;
+            match self.nodes[start.node] {
                 Node::StartArray | Node::StartObject => {
-                    if nest == 0 {
-                        count += 1;
-                    }
-                    nest += 1;
-                }
-                Node::EndArray | Node::EndObject => {
                     nest -= 1;
                 }
-                _ => {
-                    count += 1;
-                }
-            }
-        }
-        return None;
-    }
-
-    fn strip_slice(&self, node_start: usize, num_start: usize, str_start: usize) -> JsoncSlice<'a> {
-        match self.nodes[node_start] {
-            Node::StartArray | Node::StartObject => {
-                let mut nest = 1;
-                let mut node_end = node_start + 1;
-                let mut num_end = num_start;
-                let mut str_end = str_start;
-                while nest > 0 {
-                    match self.nodes[node_end] {
-                        Node::StartArray | Node::StartObject => {
-                            nest += 1;
-                        }
-                        Node::EndArray | Node::EndObject => {
-                            nest -= 1;
-                        }
-                        Node::String => {
-                            str_end += 1;
-                        }
-                        Node::Number => {
-                            num_end += 1;
-                        }
-                        Node::Key => {
-                            str_end += 1;
-                        }
-                        _ => {}
-                    }
-                    node_end += 1;
+                Node::EndArray | Node::EndObject => {
+                    nest += 1;
                 }
-                JsoncSlice {
-                    nodes: &self.nodes[node_start..node_end],
-                    strings: &self.strings[str_start..str_end],
-                    numbers: &self.numbers[num_start..num_end],
+                Node::String | Node::Key => {
+                    start.string -= 1;
                 }
-            }
-            Node::String => {
-                JsoncSlice {
-                    nodes: &self.nodes[node_start..node_start + 1],
-                    strings: &self.strings[str_start..str_start + 1],
-                    numbers: &self.numbers[num_start..num_start],
+                Node::Number => {
+                    start.number -= 1;
                 }
+                _ => {}
             }
-            Node::Number => {
-                JsoncSlice {
-                    nodes: &self.nodes[node_start..node_start + 1],
-                    strings: &self.strings[str_start..str_start],
-                    numbers: &self.numbers[num_start..num_start + 1],
-                }
+            if nest == 0 {
+                return start;
             }
-            _ => JsoncSlice {
-                nodes: &self.nodes[node_start..node_start + 1],
-                strings: &self.strings[str_start..str_start],
-                numbers: &self.numbers[num_start..num_start],
+        }
+    }
+
+    fn sub_slice(&self, start: Cursor, end: Cursor) -> JsoncSlice<'a> {
+        JsoncSlice {
+            nodes: &self.nodes[start.node..end.node],
+            strings: &self.strings[start.string..end.string],
+            numbers: &self.numbers[start.number..end.number],
+        }
+    }
+}
+
+/// A position in the three columns of a slice.
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    node: usize,
+    string: usize,
+    number: usize,
+}
+
+/// The unvisited children of a container, consumed from either end.
+#[derive(Debug, Clone)]
+struct Children<'a> {
+    container: JsoncSlice<'a>,
+    front: Cursor,
+    back: Cursor,
+}
+
+impl<'a> Children<'a> {
+    fn new(container: JsoncSlice<'a>) -> Self {
+        Self {
+            container,
+            front: Cursor {
+                node: 1,
+                string: 0,
+                number: 0,
+            },
+            back: Cursor {
+                node: container.nodes.len() - 1,
+                string: container.strings.len(),
+                number: container.numbers.len(),
             },
         }
     }
+
+    fn is_empty(&self) -> bool {
+        self.front.node >= self.back.node
+    }
+
+    fn next_value(&mut self) -> Option<JsoncSlice<'a>> {
+        if self.is_empty() {
+            return None;
+        }
+        let start = self.front;
+        self.front = self.container.value_end(start);
+        Some(self.container.sub_slice(start, self.front))
+    }
+
+    fn next_back_value(&mut self) -> Option<JsoncSlice<'a>> {
+        if self.is_empty() {
+            return None;
+        }
+        let end = self.back;
+        self.back = self.container.value_start(end);
+        Some(self.container.sub_slice(self.back, end))
+    }
+
+    fn next_entry(&mut self) -> Option<(&'a str, JsoncSlice<'a>)> {
+        if self.is_empty() {
+            return None;
+        }
+        let key = self.container.strings[self.front.string].as_str();
+        self.front.node += 1;
+        self.front.string += 1;
+        let value = self.next_value()?;
+        Some((key, value))
+    }
+
+    fn next_back_entry(&mut self) -> Option<(&'a str, JsoncSlice<'a>)> {
+        let value = self.next_back_value()?;
+        self.back.node -= 1;
+        self.back.string -= 1;
+        let key = self.container.strings[self.back.string].as_str();
+        Some((key, value))
+    }
+}
+
+/// Iterator over the elements of an array, created by [`JsoncSlice::array_iter`].
+#[derive(Debug, Clone)]
+pub struct ArrayIter<'a> {
+    children: Children<'a>,
 }
 
+impl<'a> Iterator for ArrayIter<'a> {
+    type Item = JsoncSlice<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.children.next_value()
+    }
+}
+
+impl<'a> DoubleEndedIterator for ArrayIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.children.next_back_value()
+    }
+}
+
+impl FusedIterator for ArrayIter<'_> {}
+
+/// Iterator over the entries of an object, created by [`JsoncSlice::object_iter`].
+#[derive(Debug, Clone)]
+pub struct ObjectIter<'a> {
+    children: Children<'a>,
+}
+
+impl<'a> Iterator for ObjectIter<'a> {
+    type Item = (&'a str, JsoncSlice<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.children.next_entry()
+    }
+}
+
+impl<'a> DoubleEndedIterator for ObjectIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.children.next_back_entry()
+    }
+}
+
+impl FusedIterator for ObjectIter<'_> {}
+
+/// Iterator over the keys of an object, created by [`JsoncSlice::keys`].
+#[derive(Debug, Clone)]
+pub struct Keys<'a> {
+    entries: ObjectIter<'a>,
+}
+
+impl<'a> Iterator for Keys<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(|(key, _)| key)
+    }
+}
+
+impl<'a> DoubleEndedIterator for Keys<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back().map(|(key, _)| key)
+    }
+}
+
+impl FusedIterator for Keys<'_> {}
+
 impl<'a> From<&'a Jsonc> for JsoncSlice<'a> {
     fn from(jsonc: &'a Jsonc) -> JsoncSlice<'a> {
         JsoncSlice::new(jsonc)
@@ -181,6 +379,11 @@ impl From<JsoncSlice<'_> > for Jsonc {
 mod tests {
     use super::*;
 
+    /// The columns of `json`, which `==` does not compare.
+    fn columns(json: JsoncSlice) -> (&[Node], &[String], &[f64]) {
+        (json.nodes, json.strings, json.numbers)
+    }
+
     #[test]
     fn test_jsonc_get() {
         let jsonc = Jsonc {
@@ -202,18 +405,50 @@ mod tests {
         };
         let jsonc_slice = JsoncSlice::new(&jsonc);
         let result = jsonc_slice.get_by_path("key1");
-        assert_eq!(result, Some(JsoncSlice {
+        assert_eq!(result.map(columns), Some(columns(JsoncSlice {
             nodes: &[Node::String],
             strings: &["value1".to_string()],
             numbers: &[],
-        }));
+        })));
         let result = jsonc_slice.get_by_path("key2");
-        assert_eq!(result, Some(JsoncSlice {
+        assert_eq!(result.map(columns), Some(columns(JsoncSlice {
             nodes: &[Node::String],
             strings: &["value2".to_string()],
             numbers: &[],
-        }));
+        })));
         let result = jsonc_slice.get_by_path("key3");
         assert_eq!(result.is_none(), true);
     }
+
+    #[test]
+    fn test_jsonc_iter() {
+        let jsonc =
+            crate::parser::parse_value(br#"{"a":[1,{"b":"c"},[2,3],"d",null],"e":{},"f":4}"#);
+        let slice = jsonc.as_slice();
+        let keys: Vec<&str> = slice.keys().unwrap().collect();
+        assert_eq!(keys, ["a", "e", "f"]);
+        let keys: Vec<&str> = slice.keys().unwrap().rev().collect();
+        assert_eq!(keys, ["f", "e", "a"]);
+
+        let mut entries = slice.object_iter().unwrap();
+        let (key, last) = entries.next_back().unwrap();
+        assert_eq!((key, last.as_f64()), ("f", Some(4.0)));
+        let (key, array) = entries.next().unwrap();
+        assert_eq!(key, "a");
+        let (key, empty) = entries.next().unwrap();
+        assert_eq!((key, empty.len()), ("e", Some(0)));
+        assert!(entries.next().is_none());
+        assert!(entries.next_back().is_none());
+
+        let mut elements = array.array_iter().unwrap();
+        assert!(elements.next_back().unwrap().is_null());
+        assert_eq!(elements.next().unwrap().as_f64(), Some(1.0));
+        assert_eq!(elements.next_back().unwrap().as_str(), Some("d"));
+        let nested = elements.next_back().unwrap();
+        assert_eq!(nested.get_by_idx(1).unwrap().as_f64(), Some(3.0));
+        let object = elements.next().unwrap();
+        assert_eq!(object.get_by_path("b").unwrap().as_str(), Some("c"));
+        assert!(elements.next().is_none());
+        assert!(slice.array_iter().is_none());
+    }
 }
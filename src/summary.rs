@@ -0,0 +1,192 @@
+use std::fmt::Write as _;
+
+use crate::decoder::{write_scalar, write_string, FmtSink, LOSSY};
+use crate::slice::{JsoncSlice, ValueKind};
+
+/// The limits of [`summarize`]. The defaults suit a log line.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SummaryOptions {
+    /// Arrays and objects nested deeper than this are written as `[…]` and
+    /// `{…}`; the value itself is at depth 0. The default is 3.
+    pub max_depth: usize,
+    /// The elements written of each array. The default is 8.
+    pub max_array_elements: usize,
+    /// The entries written of each object. The default is 16.
+    pub max_object_entries: usize,
+    /// The characters written of each string. The default is 64.
+    pub max_string_chars: usize,
+    /// The length of the summary in bytes, after which the remaining
+    /// elements and entries are elided. The closing brackets and elision
+    /// markers may go a little past it. The default is 1024.
+    pub max_bytes: usize,
+}
+
+impl Default for SummaryOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            max_array_elements: 8,
+            max_object_entries: 16,
+            max_string_chars: 64,
+            max_bytes: 1024,
+        }
+    }
+}
+
+/// Writes `json` as compact JSON cut down to the limits of `options`, for
+/// logging large documents, such as
+/// `{"statuses":[{…},{…}, … 98 more],"search_metadata":{…}}`.
+///
+/// An array or object that is too deep is written as `[…]` or `{…}`, and
+/// the elements or entries left out at the end of one as `… 98 more`. A
+/// string that is too long ends with `…` after its closing quote and the
+/// number of characters left out, as in `"Lorem ipsum"… (+120 chars)`. The
+/// result is JSON only when nothing is elided.
+pub fn summarize(json: JsoncSlice, options: &SummaryOptions) -> String {
+    let mut summary = Summary {
+        options,
+        result: String::new(),
+    };
+    summary.value(json, 0);
+    summary.result
+}
+
+struct Summary<'o> {
+    options: &'o SummaryOptions,
+    result: String,
+}
+
+impl Summary<'_> {
+    fn value(&mut self, json: JsoncSlice, depth: usize) {
+        match json.kind() {
+            Some(ValueKind::Array) => self.array(json, depth),
+            Some(ValueKind::Object) => self.object(json, depth),
+            Some(ValueKind::String) => self.string(json.as_str().unwrap()),
+            _ => {
+                let _ = write_scalar(&mut FmtSink(&mut self.result), json, &LOSSY);
+            }
+        }
+    }
+
+    fn array(&mut self, json: JsoncSlice, depth: usize) {
+        let len = json.len().unwrap();
+        if len > 0 && depth >= self.options.max_depth {
+            self.result.push_str("[…]");
+            return;
+        }
+        self.result.push('[');
+        for (i, element) in json.array_iter().unwrap().enumerate() {
+            if !self.next_child(i, len, self.options.max_array_elements) {
+                break;
+            }
+            self.value(element, depth + 1);
+        }
+        self.result.push(']');
+    }
+
+    fn object(&mut self, json: JsoncSlice, depth: usize) {
+        let len = json.len().unwrap();
+        if len > 0 && depth >= self.options.max_depth {
+            self.result.push_str("{…}");
+            return;
+        }
+        self.result.push('{');
+        for (i, (key, value)) in json.object_iter().unwrap().enumerate() {
+            if !self.next_child(i, len, self.options.max_object_entries) {
+                break;
+            }
+            self.string(key);
+            self.result.push(':');
+            self.value(value, depth + 1);
+        }
+        self.result.push('}');
+    }
+
+    /// Writes what comes before child `i` of `len`, returning whether to
+    /// write the child or, having marked the rest as elided, stop. Once the
+    /// summary is `max_bytes` long, every open container stops.
+    fn next_child(&mut self, i: usize, len: usize, max: usize) -> bool {
+        if i >= max || self.result.len() >= self.options.max_bytes {
+            if i > 0 {
+                self.result.push_str(", ");
+            }
+            let _ = write!(self.result, "… {} more", len - i);
+            return false;
+        }
+        if i > 0 {
+            self.result.push(',');
+        }
+        true
+    }
+
+    fn string(&mut self, s: &str) {
+        let max = self.options.max_string_chars;
+        let Some((end, _)) = s.char_indices().nth(max) else {
+            let _ = write_string(&mut FmtSink(&mut self.result), s, LOSSY.escape);
+            return;
+        };
+        let _ = write_string(&mut FmtSink(&mut self.result), &s[..end], LOSSY.escape);
+        let rest = s[end..].chars().count();
+        let _ = write!(self.result, "… (+{} chars)", rest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_value;
+
+    fn summary(json: &str, options: &SummaryOptions) -> String {
+        summarize(parse_value(json.as_bytes()).as_slice(), options)
+    }
+
+    #[test]
+    fn test_summarize() {
+        let options = SummaryOptions::default();
+        let json = r#"{"a":[1,"x",null,true,{}],"b":{"c":{"d":{"e":[]}}},"s":"short"}"#;
+        assert_eq!(
+            summary(json, &options),
+            r#"{"a":[1,"x",null,true,{}],"b":{"c":{"d":{…}}},"s":"short"}"#
+        );
+
+        let options = SummaryOptions {
+            max_depth: 1,
+            max_array_elements: 2,
+            max_object_entries: 1,
+            max_string_chars: 3,
+            ..SummaryOptions::default()
+        };
+        assert_eq!(summary(r#"[1,2,3,4]"#, &options), "[1,2, … 2 more]");
+        assert_eq!(summary(r#"[1,2]"#, &options), "[1,2]");
+        assert_eq!(
+            summary(r#"{"a":1,"b":2}"#, &options),
+            r#"{"a":1, … 1 more}"#
+        );
+        assert_eq!(
+            summary(r#"[[1],{"a":1},[],{}]"#, &options),
+            "[[…],{…}, … 2 more]"
+        );
+        assert_eq!(
+            summary(r#"["abcdef","abc","é\"€😀x"]"#, &options),
+            r#"["abc"… (+3 chars),"abc", … 1 more]"#
+        );
+        assert_eq!(summary(r#""é\"€😀x""#, &options), r#""é\"€"… (+2 chars)"#);
+    }
+
+    #[test]
+    fn test_summarize_max_bytes() {
+        let options = SummaryOptions {
+            max_bytes: 10,
+            ..SummaryOptions::default()
+        };
+        assert_eq!(
+            summary(r#"[[1,2,3,4,5,6],[7],8]"#, &options),
+            "[[1,2,3,4,5, … 1 more], … 2 more]"
+        );
+        assert_eq!(
+            summary(r#"{"abcdefghij":[1,2],"k":0}"#, &options),
+            r#"{"abcdefghij":[… 2 more], … 1 more}"#
+        );
+        assert_eq!(summary("[1,2]", &options), "[1,2]");
+    }
+}
@@ -1,55 +1,70 @@
-use crate::{decoder::decode_slice, slice::JsoncSlice};
+use std::cmp::Ordering;
 
-#[derive(Debug, PartialEq, Clone)]
+use crate::decoder::decode_slice;
+use crate::error::{Error, Result};
+use crate::path::IntoJsonPath;
+use crate::slice::{JsoncSlice, ValueKind};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
 pub enum Node {
-    Null,
-    StartArray,
-    EndArray,
-    StartObject,
-    EndObject,
-    Key,
-    String,
-    Number,
-    True,
-    False,
+    Null = 0,
+    StartArray = 1,
+    EndArray = 2,
+    StartObject = 3,
+    EndObject = 4,
+    Key = 5,
+    String = 6,
+    Number = 7,
+    True = 8,
+    False = 9,
+}
+
+impl Node {
+    /// Views a node column as its `u8` tags without copying.
+    pub fn as_bytes(nodes: &[Node]) -> &[u8] {
+        // SAFETY: `Node` is `repr(u8)`, so it has the size and alignment of `u8`.
+        unsafe { std::slice::from_raw_parts(nodes.as_ptr() as *const u8, nodes.len()) }
+    }
+
+    /// Views a column of `u8` tags as nodes without copying, failing on the
+    /// first byte that is not a valid tag.
+    pub fn from_bytes(bytes: &[u8]) -> Result<&[Node]> {
+        if let Some(tag) = bytes.iter().find(|tag| **tag > Node::False as u8) {
+            return Err(Error::InvalidNode(*tag));
+        }
+        // SAFETY: every byte was checked to be a valid `Node` discriminant above.
+        Ok(unsafe { std::slice::from_raw_parts(bytes.as_ptr() as *const Node, bytes.len()) })
+    }
 }
 
 impl From<&Node> for u8 {
     fn from(node: &Node) -> u8 {
-        match node {
-            Node::Null => 0,
-            Node::StartArray => 1,
-            Node::EndArray => 2,
-            Node::StartObject => 3,
-            Node::EndObject => 4,
-            Node::Key => 5,
-            Node::String => 6,
-            Node::Number => 7,
-            Node::True => 8,
-            Node::False => 9,
-        }
+        *node as u8
     }
 }
 
-impl From<&u8> for Node {
-    fn from(n: &u8) -> Node {
+impl TryFrom<u8> for Node {
+    type Error = Error;
+
+    fn try_from(n: u8) -> Result<Node> {
         match n {
-            0 => Node::Null,
-            1 => Node::StartArray,
-            2 => Node::EndArray,
-            3 => Node::StartObject,
-            4 => Node::EndObject,
-            5 => Node::Key,
-            6 => Node::String,
-            7 => Node::Number,
-            8 => Node::True,
-            9 => Node::False,
-            _ => panic!("Invalid node value"),
+            0 => Ok(Node::Null),
+            1 => Ok(Node::StartArray),
+            2 => Ok(Node::EndArray),
+            3 => Ok(Node::StartObject),
+            4 => Ok(Node::EndObject),
+            5 => Ok(Node::Key),
+            6 => Ok(Node::String),
+            7 => Ok(Node::Number),
+            8 => Ok(Node::True),
+            9 => Ok(Node::False),
+            _ => Err(Error::InvalidNode(n)),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Default, Clone)]
+#[derive(Default, Clone)]
 pub struct Jsonc {
     pub nodes: Vec<Node>,
     pub strings: Vec<String>,
@@ -79,12 +94,22 @@ impl Jsonc {
         self.numbers.append(&mut other.numbers);
     }
 
+    /// Creates a `Jsonc` from a node column stored as `u8` tags.
+    pub fn from_node_bytes(nodes: &[u8], strings: Vec<String>, numbers: Vec<f64>) -> Result<Self> {
+        Ok(Self::new_with_values(
+            Node::from_bytes(nodes)?.to_vec(),
+            strings,
+            numbers,
+        ))
+    }
+
+    /// The node column as `u8` tags, borrowed without conversion.
+    pub fn node_bytes(&self) -> &[u8] {
+        Node::as_bytes(&self.nodes)
+    }
+
     pub fn node_opt_list(&self) -> Vec<Option<u8>> {
-        let mut node_list = Vec::new();
-        for node in self.nodes.iter() {
-            node_list.push(Some(node.into()));
-        }
-        node_list
+        self.node_bytes().iter().copied().map(Some).collect()
     }
 
     pub fn string_opt_list(&self) -> Vec<Option<String>> {
@@ -99,24 +124,107 @@ impl Jsonc {
         self.into()
     }
 
-    pub fn get(&self, paths: &[&str]) -> Option<String> {
-        let mut json_slice = self.as_slice();
-        for path in paths {
-            if path.starts_with("\"") {
-                if let Some(slice) = json_slice.get_by_path(&path[1..path.len() - 1]) {
-                    json_slice = slice;
-                } else {
-                    return None;
-                }
-            } else {
-                let idx = path.parse::<usize>().unwrap();
-                if let Some(slice) = json_slice.get_by_idx(idx) {
-                    json_slice = slice;
-                } else {
-                    return None;
-                }
+    /// Sorts the entries of every object by the bytes of their keys,
+    /// rewriting the columns. Entries with the same key keep their order.
+    pub fn sort_keys(&mut self) {
+        self.sort_keys_by(str::cmp)
+    }
+
+    /// Like [`Jsonc::sort_keys`], ordering keys by `compare`.
+    pub fn sort_keys_by<F: FnMut(&str, &str) -> Ordering>(&mut self, mut compare: F) {
+        let mut sorted = Jsonc::new_with_values(
+            Vec::with_capacity(self.nodes.len()),
+            Vec::with_capacity(self.strings.len()),
+            Vec::with_capacity(self.numbers.len()),
+        );
+        push_sorted(&mut sorted, self.as_slice(), &mut compare);
+        *self = sorted;
+    }
+
+    /// Looks up a value by a path such as `search_metadata.max_id_str` and
+    /// returns it as JSON text. See [`JsonPath`](crate::path::JsonPath) for the path syntax.
+    pub fn get<P: IntoJsonPath>(&self, path: P) -> Result<Option<String>> {
+        Ok(self.as_slice().get(path)?.map(decode_slice))
+    }
+}
+
+fn push_sorted<F: FnMut(&str, &str) -> Ordering>(
+    jsonc: &mut Jsonc,
+    json: JsoncSlice,
+    compare: &mut F,
+) {
+    match json.kind() {
+        Some(ValueKind::Object) => {
+            let mut entries: Vec<_> = json.object_iter().unwrap().collect();
+            entries.sort_by(|(a, _), (b, _)| compare(a, b));
+            jsonc.nodes.push(Node::StartObject);
+            for (key, value) in entries {
+                jsonc.nodes.push(Node::Key);
+                jsonc.strings.push(key.to_string());
+                push_sorted(jsonc, value, compare);
+            }
+            jsonc.nodes.push(Node::EndObject);
+        }
+        Some(ValueKind::Array) => {
+            jsonc.nodes.push(Node::StartArray);
+            for element in json.array_iter().unwrap() {
+                push_sorted(jsonc, element, compare);
             }
+            jsonc.nodes.push(Node::EndArray);
+        }
+        _ => {
+            jsonc.nodes.extend_from_slice(json.nodes);
+            jsonc.strings.extend_from_slice(json.strings);
+            jsonc.numbers.extend_from_slice(json.numbers);
         }
-        Some(decode_slice(json_slice))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::decode;
+    use crate::parser::parse_value;
+
+    #[test]
+    fn test_node_bytes() {
+        let jsonc = Jsonc::new_with_values(
+            vec![Node::StartArray, Node::Null, Node::True, Node::EndArray],
+            vec![],
+            vec![],
+        );
+        assert_eq!(jsonc.node_bytes(), &[1, 0, 8, 2]);
+        assert_eq!(
+            Node::from_bytes(jsonc.node_bytes()).unwrap(),
+            &jsonc.nodes[..]
+        );
+        assert!(matches!(
+            Node::from_bytes(&[1, 10, 2]),
+            Err(Error::InvalidNode(10))
+        ));
+        assert!(matches!(Node::try_from(7), Ok(Node::Number)));
+        assert!(matches!(Node::try_from(255), Err(Error::InvalidNode(255))));
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let mut jsonc = parse_value(br#"{"b":[{"z":1,"y":"s"}],"a":{"d":null,"c":true},"b":0}"#);
+        jsonc.sort_keys();
+        assert_eq!(
+            decode(&jsonc),
+            r#"{"a":{"c":true,"d":null},"b":[{"y":"s","z":1}],"b":0}"#
+        );
+        assert_eq!(jsonc.strings.len(), 8);
+        assert_eq!(jsonc.numbers, [1.0, 0.0]);
+
+        jsonc.sort_keys_by(|a, b| b.cmp(a));
+        assert_eq!(
+            decode(&jsonc),
+            r#"{"b":[{"z":1,"y":"s"}],"b":0,"a":{"d":null,"c":true}}"#
+        );
+
+        let mut scalar = parse_value(b"1");
+        scalar.sort_keys();
+        assert_eq!(decode(&scalar), "1");
     }
 }
@@ -0,0 +1,201 @@
+use crate::path::PathItem;
+use crate::slice::JsoncSlice;
+use crate::value::Node;
+
+/// Callbacks for a depth-first walk over a JSON value, driven by [`walk`].
+///
+/// Every callback receives the path from the root to the value it reports and
+/// the depth of that value, where the root is at depth 0. The callbacks do
+/// nothing by default, so a visitor only implements the events it needs.
+/// Returning an error stops the walk.
+pub trait Visitor<'a> {
+    type Error;
+
+    fn start_object(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn end_object(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn start_array(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn end_array(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called before the value of an object entry, with `path` already
+    /// ending in `key`.
+    fn key(
+        &mut self,
+        _key: &'a str,
+        _path: &[PathItem<'a>],
+        _depth: usize,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn string(
+        &mut self,
+        _value: &'a str,
+        _path: &[PathItem<'a>],
+        _depth: usize,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn number(
+        &mut self,
+        _value: f64,
+        _path: &[PathItem<'a>],
+        _depth: usize,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn bool(
+        &mut self,
+        _value: bool,
+        _path: &[PathItem<'a>],
+        _depth: usize,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn null(&mut self, _path: &[PathItem<'a>], _depth: usize) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+enum Frame {
+    /// An open array and the index of its next element.
+    Array(usize),
+    Object,
+}
+
+/// Walks `json` in document order, reporting every value to `visitor`.
+pub fn walk<'a, V>(json: JsoncSlice<'a>, visitor: &mut V) -> Result<(), V::Error>
+where
+    V: Visitor<'a> + ?Sized,
+{
+    let mut path = Vec::new();
+    let mut frames = Vec::new();
+    let mut iter_str = json.strings.iter();
+    let mut iter_num = json.numbers.iter();
+    for node in json.nodes {
+        let depth = frames.len();
+        if let Some(Frame::Array(idx)) = frames.last_mut() {
+            if *node != Node::EndArray {
+                path.push(PathItem::Index(*idx));
+                *idx += 1;
+            }
+        }
+        match node {
+            Node::Key => {
+                let key = iter_str.next().unwrap();
+                path.push(PathItem::Key(key));
+                visitor.key(key, &path, depth)?;
+                continue;
+            }
+            Node::StartArray => {
+                visitor.start_array(&path, depth)?;
+                frames.push(Frame::Array(0));
+                continue;
+            }
+            Node::StartObject => {
+                visitor.start_object(&path, depth)?;
+                frames.push(Frame::Object);
+                continue;
+            }
+            Node::EndArray => {
+                frames.pop();
+                visitor.end_array(&path, depth - 1)?;
+            }
+            Node::EndObject => {
+                frames.pop();
+                visitor.end_object(&path, depth - 1)?;
+            }
+            Node::String => visitor.string(iter_str.next().unwrap(), &path, depth)?,
+            Node::Number => visitor.number(*iter_num.next().unwrap(), &path, depth)?,
+            Node::True => visitor.bool(true, &path, depth)?,
+            Node::False => visitor.bool(false, &path, depth)?,
+            Node::Null => visitor.null(&path, depth)?,
+        }
+        // The value is complete, so step back out to its container.
+        if !frames.is_empty() {
+            path.pop();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_value;
+
+    /// Records every scalar with the path it was reported at.
+    #[derive(Default)]
+    struct Scalars<'a> {
+        seen: Vec<(Vec<PathItem<'a>>, usize)>,
+        max_depth: usize,
+    }
+
+    impl<'a> Visitor<'a> for Scalars<'a> {
+        type Error = ();
+
+        fn start_array(&mut self, _path: &[PathItem<'a>], depth: usize) -> Result<(), ()> {
+            self.max_depth = self.max_depth.max(depth);
+            Ok(())
+        }
+
+        fn number(&mut self, _value: f64, path: &[PathItem<'a>], depth: usize) -> Result<(), ()> {
+            self.seen.push((path.to_vec(), depth));
+            Ok(())
+        }
+
+        fn string(
+            &mut self,
+            value: &'a str,
+            path: &[PathItem<'a>],
+            depth: usize,
+        ) -> Result<(), ()> {
+            if value == "stop" {
+                return Err(());
+            }
+            self.seen.push((path.to_vec(), depth));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_walk_paths() {
+        let jsonc = parse_value(br#"{"a":[1,{"b":"x"}],"c":[[2]],"d":3}"#);
+        let mut scalars = Scalars::default();
+        walk(jsonc.as_slice(), &mut scalars).unwrap();
+        assert_eq!(scalars.max_depth, 2);
+        assert_eq!(
+            scalars.seen,
+            vec![
+                (vec![PathItem::Key("a"), PathItem::Index(0)], 2),
+                (
+                    vec![PathItem::Key("a"), PathItem::Index(1), PathItem::Key("b")],
+                    3
+                ),
+                (
+                    vec![PathItem::Key("c"), PathItem::Index(0), PathItem::Index(0)],
+                    3
+                ),
+                (vec![PathItem::Key("d")], 1),
+            ]
+        );
+
+        let jsonc = parse_value(br#"["go","stop",1]"#);
+        let mut scalars = Scalars::default();
+        assert!(walk(jsonc.as_slice(), &mut scalars).is_err());
+        assert_eq!(scalars.seen, vec![(vec![PathItem::Index(0)], 1)]);
+    }
+}
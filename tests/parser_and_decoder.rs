@@ -1,5 +1,12 @@
-use jsonc::decoder::decode;
-use jsonc::parser::parse_value;
+use std::io;
+use std::sync::Arc;
+
+use jsonc::decoder::{
+    decode, decode_pretty, decode_pretty_with, decode_slice, decode_with, encode_to, encode_to_fmt,
+    encode_to_with, DecodeOptions, Escape, Indent, KeyOrder, Newline, NonFinite, PrettyOptions,
+};
+use jsonc::error::Error;
+use jsonc::parser::{parse_concatenated, parse_prefix, parse_value, try_parse_value};
 use jsonc::value::{Jsonc, Node};
 
 fn test_parser() {
@@ -44,7 +51,62 @@ fn test_parser() {
     expected_json.strings = strings;
     expected_json.numbers = numbers;
 
-    assert_eq!(parsed_json, expected_json);
+    assert_eq!(columns(&parsed_json), columns(&expected_json));
+
+    assert_eq!(
+        columns(&try_parse_value(json).unwrap()),
+        columns(&expected_json)
+    );
+    for (json, expected) in [
+        (&b"[1, 2"[..], "Unexpected EOF at position 5"),
+        (b"{\"a\" 1}", "Unexpected character at position 5"),
+        (b"{1: 2}", "Expected string key at position 2"),
+        (b"01", "Invalid number value at position 1"),
+        (b"null x", "Unexpected trailing characters at position 5"),
+    ] {
+        assert_eq!(try_parse_value(json).unwrap_err().to_string(), expected);
+    }
+}
+
+fn test_parse_prefix() {
+    let line = b" {\"level\": \"warn\", \"ids\": [1, 2]} \t(trailer) {";
+    let (json, len) = parse_prefix(line).unwrap();
+    assert_eq!(len, 33);
+    assert_eq!(decode(&json), r#"{"level":"warn","ids":[1,2]}"#);
+    assert_eq!(&line[len..], b" \t(trailer) {");
+
+    assert_eq!(parse_prefix(b"12 34").unwrap().1, 2);
+    assert_eq!(parse_prefix(b"\"a\"b").unwrap().1, 3);
+    assert!(matches!(
+        parse_prefix(b"  ]"),
+        Err(Error::Syntax { pos: 2, .. })
+    ));
+
+    let values: Vec<_> = parse_concatenated(b"{\"a\":1}{\"a\":2}\n[3]\"x\"true null 4 ")
+        .map(|json| decode(&json.unwrap()))
+        .collect();
+    assert_eq!(
+        values,
+        [
+            r#"{"a":1}"#,
+            r#"{"a":2}"#,
+            "[3]",
+            r#""x""#,
+            "true",
+            "null",
+            "4"
+        ]
+    );
+
+    let mut values = parse_concatenated(b"[1] [2, {\"a\"} [3]");
+    assert_eq!(decode(&values.next().unwrap().unwrap()), "[1]");
+    assert_eq!(values.position(), 3);
+    assert!(matches!(
+        values.next(),
+        Some(Err(Error::Syntax { pos: 12, .. }))
+    ));
+    assert!(values.next().is_none());
+    assert!(parse_concatenated(b" \n ").next().is_none());
 }
 
 fn test_decoder() {
@@ -65,8 +127,330 @@ fn test_decoder() {
     assert_eq!(decoded_json, expected_json);
 }
 
+fn test_decode_pretty() {
+    let parsed_json =
+        parse_value(br#"{"name":"John Doe","scores":[100,98],"tags":[],"nested":{"a":[1,{}]}}"#);
+    let pretty = |options: &PrettyOptions| decode_pretty(parsed_json.as_slice(), options);
+
+    let expected = r#"{
+  "name": "John Doe",
+  "scores": [
+    100,
+    98
+  ],
+  "tags": [],
+  "nested": {
+    "a": [
+      1,
+      {}
+    ]
+  }
+}"#;
+    assert_eq!(pretty(&PrettyOptions::default()), expected);
+
+    let options = PrettyOptions {
+        indent: Indent::Tabs(1),
+        newline: Newline::CrLf,
+        space_after_colon: false,
+        compact_scalar_arrays: true,
+        compact_array_max_width: None,
+        key_order: KeyOrder::Document,
+        escape: Escape::default(),
+        non_finite: NonFinite::Error,
+    };
+    let expected = "{\r\n\t\"name\":\"John Doe\",\r\n\t\"scores\":[100, 98],\r\n\t\"tags\":[],\r\n\t\"nested\":{\r\n\t\t\"a\":[\r\n\t\t\t1,\r\n\t\t\t{}\r\n\t\t]\r\n\t}\r\n}";
+    assert_eq!(pretty(&options), expected);
+
+    // `  "scores": [100, 98],` is 22 characters wide, with its comma.
+    let options = PrettyOptions {
+        compact_scalar_arrays: true,
+        compact_array_max_width: Some(22),
+        ..PrettyOptions::default()
+    };
+    assert!(pretty(&options).contains(r#""scores": [100, 98],"#));
+    let options = PrettyOptions {
+        compact_array_max_width: Some(21),
+        ..options
+    };
+    assert!(pretty(&options).contains("\"scores\": [\n    100,\n    98\n  ],"));
+
+    let scalar = parse_value(br#""a\"b""#);
+    assert_eq!(
+        decode_pretty(scalar.as_slice(), &PrettyOptions::default()),
+        r#""a\"b""#
+    );
+}
+
+/// A writer that fails once it has accepted `capacity` bytes.
+struct FullWriter {
+    capacity: usize,
+}
+
+impl io::Write for FullWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.capacity {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "full"));
+        }
+        self.capacity -= buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn test_encode_to() {
+    let json_str = std::fs::read_to_string("data/twitter.json").unwrap();
+    let parsed_json = parse_value(json_str.as_bytes());
+    let expected = decode(&parsed_json);
+    let mut bytes = Vec::new();
+    encode_to(parsed_json.as_slice(), &mut bytes).unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(), expected);
+    let mut string = String::new();
+    encode_to_fmt(parsed_json.as_slice(), &mut string).unwrap();
+    assert_eq!(string, expected);
+
+    let parsed_json = parse_value(br#"{"a\"b":["x\ny",1,{},[[]],true,null],"\u0001":""}"#);
+    let mut string = String::new();
+    encode_to_fmt(parsed_json.as_slice(), &mut string).unwrap();
+    assert_eq!(
+        string,
+        r#"{"a\"b":["x\ny",1,{},[[]],true,null],"\u0001":""}"#
+    );
+
+    let mut writer = FullWriter { capacity: 10 };
+    assert!(matches!(
+        encode_to(parsed_json.as_slice(), &mut writer),
+        Err(Error::Io(_))
+    ));
+}
+
+fn test_decode_numbers() {
+    let parsed_json =
+        parse_value(b"[1e300, 1e-7, 0.5, 1000, 1e21, 123456789, -0.0001, 2.5e-8, 0.01]");
+    assert_eq!(
+        decode(&parsed_json),
+        "[1e300,1e-7,0.5,1000,1e21,123456789,-1e-4,2.5e-8,0.01]"
+    );
+
+    let parsed_json = Jsonc::new_with_values(
+        vec![
+            Node::StartArray,
+            Node::Number,
+            Node::Number,
+            Node::Number,
+            Node::EndArray,
+        ],
+        vec![],
+        vec![f64::NAN, f64::INFINITY, f64::NEG_INFINITY],
+    );
+    let decode_with_policy = |non_finite| {
+        decode_with(
+            parsed_json.as_slice(),
+            &DecodeOptions {
+                non_finite,
+                ..DecodeOptions::default()
+            },
+        )
+    };
+    assert!(matches!(
+        decode_with_policy(NonFinite::Error),
+        Err(Error::NonFiniteNumber(n)) if n.is_nan()
+    ));
+    assert_eq!(
+        decode_with_policy(NonFinite::Null).unwrap(),
+        "[null,null,null]"
+    );
+    assert_eq!(
+        decode_with_policy(NonFinite::Literal).unwrap(),
+        "[NaN,Infinity,-Infinity]"
+    );
+    assert_eq!(decode_slice(parsed_json.as_slice()), "[null,null,null]");
+    let pretty = |non_finite| {
+        let options = PrettyOptions {
+            non_finite,
+            indent: Indent::Spaces(0),
+            ..PrettyOptions::default()
+        };
+        decode_pretty_with(parsed_json.as_slice(), &options).map(|s| s.replace('\n', ""))
+    };
+    assert!(matches!(
+        pretty(NonFinite::Error),
+        Err(Error::NonFiniteNumber(n)) if n.is_nan()
+    ));
+    assert_eq!(pretty(NonFinite::Null).unwrap(), "[null,null,null]");
+    assert_eq!(
+        pretty(NonFinite::Literal).unwrap(),
+        "[NaN,Infinity,-Infinity]"
+    );
+    assert_eq!(
+        decode_pretty(parsed_json.as_slice(), &PrettyOptions::default()),
+        "[\n  null,\n  null,\n  null\n]"
+    );
+    assert!(encode_to(parsed_json.as_slice(), &mut Vec::new()).is_err());
+}
+
+fn test_decode_sorted() {
+    let parsed_json = parse_value(br#"{"b":1,"a":{"y":[{"d":0,"c":0}],"x":null},"B":2}"#);
+    let sorted = |key_order| {
+        let options = DecodeOptions {
+            key_order,
+            ..DecodeOptions::default()
+        };
+        let mut bytes = Vec::new();
+        encode_to_with(parsed_json.as_slice(), &mut bytes, &options).unwrap();
+        let result = String::from_utf8(bytes).unwrap();
+        assert_eq!(
+            decode_with(parsed_json.as_slice(), &options).unwrap(),
+            result
+        );
+        result
+    };
+    assert_eq!(
+        sorted(KeyOrder::Bytes),
+        r#"{"B":2,"a":{"x":null,"y":[{"c":0,"d":0}]},"b":1}"#
+    );
+    assert_eq!(
+        sorted(KeyOrder::By(Arc::new(|a: &str, b: &str| a
+            .to_lowercase()
+            .cmp(&b.to_lowercase())
+            .then(b.cmp(a))))),
+        r#"{"a":{"x":null,"y":[{"c":0,"d":0}]},"b":1,"B":2}"#
+    );
+    let first = "b".to_string();
+    assert_eq!(
+        sorted(KeyOrder::By(Arc::new(move |a: &str, b: &str| {
+            (b == first).cmp(&(a == first)).then(a.cmp(b))
+        }))),
+        r#"{"b":1,"B":2,"a":{"x":null,"y":[{"c":0,"d":0}]}}"#
+    );
+    assert_eq!(sorted(KeyOrder::Document), decode(&parsed_json));
+
+    let options = PrettyOptions {
+        indent: Indent::Spaces(0),
+        newline: Newline::Lf,
+        key_order: KeyOrder::Bytes,
+        ..PrettyOptions::default()
+    };
+    assert_eq!(
+        decode_pretty(parsed_json.as_slice(), &options).replace('\n', ""),
+        r#"{"B": 2,"a": {"x": null,"y": [{"c": 0,"d": 0}]},"b": 1}"#
+    );
+}
+
+fn test_decode_escaped() {
+    let parsed_json = parse_value(
+        r#"{"\u00e9<k>":"a&b \u2028 \u2029 \ud83d\ude00 \u00fc </script>","\u0007":"\u007f"}"#
+            .as_bytes(),
+    );
+    let escaped = |ascii_only, html_safe| {
+        let options = DecodeOptions {
+            escape: Escape {
+                ascii_only,
+                html_safe,
+            },
+            ..DecodeOptions::default()
+        };
+        decode_with(parsed_json.as_slice(), &options).unwrap()
+    };
+    assert_eq!(
+        escaped(false, false),
+        "{\"\u{e9}<k>\":\"a&b \u{2028} \u{2029} \u{1f600} \u{fc} </script>\",\"\\u0007\":\"\u{7f}\"}"
+    );
+    assert_eq!(
+        escaped(true, false),
+        concat!(
+            r#"{"\u00e9<k>":"a&b \u2028 \u2029 \ud83d\ude00 \u00fc </script>","\u0007":""#,
+            "\u{7f}\"}"
+        )
+    );
+    assert_eq!(
+        escaped(false, true),
+        "{\"\u{e9}\\u003ck\\u003e\":\"a\\u0026b \\u2028 \\u2029 \u{1f600} \u{fc} \\u003c/script\\u003e\",\"\\u0007\":\"\u{7f}\"}"
+    );
+    let both = escaped(true, true);
+    assert!(both.is_ascii());
+    assert_eq!(parse_value(both.as_bytes()), parsed_json);
+
+    let options = PrettyOptions {
+        escape: Escape {
+            ascii_only: true,
+            html_safe: true,
+        },
+        ..PrettyOptions::default()
+    };
+    let pretty = decode_pretty(parsed_json.as_slice(), &options);
+    assert!(pretty.starts_with("{\n  \"\\u00e9\\u003ck\\u003e\": \"a\\u0026b"));
+}
+
+fn test_display() {
+    let parsed_json = parse_value(br#"{"a":[1,"x"],"b":{}}"#);
+    assert_eq!(parsed_json.to_string(), r#"{"a":[1,"x"],"b":{}}"#);
+    assert_eq!(
+        format!("{:#}", parsed_json),
+        "{\n  \"a\": [\n    1,\n    \"x\"\n  ],\n  \"b\": {}\n}"
+    );
+    let a = parsed_json.as_slice().get_by_path("a").unwrap();
+    assert_eq!(format!("{}", a), r#"[1,"x"]"#);
+    assert_eq!(format!("{:?}", a), r#"JsoncSlice([1,"x"])"#);
+    assert_eq!(
+        format!("{:?}", parsed_json),
+        r#"Jsonc({"a":[1,"x"],"b":{}})"#
+    );
+    assert_eq!(
+        format!("{:#?}", a),
+        "JsoncSlice(\n    [\n      1,\n      \"x\"\n    ],\n)"
+    );
+
+    let malformed = Jsonc::new_with_values(vec![Node::StartArray, Node::String], vec![], vec![]);
+    assert_eq!(
+        format!("{:?}", malformed),
+        "Jsonc { nodes: [StartArray, String], strings: [], numbers: [] }"
+    );
+    let two_values = Jsonc::new_with_values(vec![Node::Null, Node::Null], vec![], vec![]);
+    assert_eq!(
+        format!("{:?}", two_values),
+        "Jsonc { nodes: [Null, Null], strings: [], numbers: [] }"
+    );
+    let no_value = Jsonc::new_with_values(
+        vec![Node::StartObject, Node::Key, Node::EndObject],
+        vec!["a".to_string()],
+        vec![],
+    );
+    assert_eq!(
+        format!("{:#?}", no_value),
+        "Jsonc {\n    nodes: [\n        StartObject,\n        Key,\n        EndObject,\n    ],\n    strings: [\n        \"a\",\n    ],\n    numbers: [],\n}"
+    );
+    assert_eq!(
+        format!("{:?}", Jsonc::default()),
+        "Jsonc { nodes: [], strings: [], numbers: [] }"
+    );
+    assert_eq!(
+        malformed.to_string(),
+        "Jsonc { nodes: [StartArray, String], strings: [], numbers: [] }"
+    );
+    let nan = Jsonc::new_with_values(vec![Node::Number], vec![], vec![f64::NAN]);
+    assert_eq!(nan.to_string(), "null");
+    assert_eq!(format!("{:?}", nan), "Jsonc(NaN)");
+    assert_ne!(format!("{:?}", nan), format!("{:?}", parse_value(b"null")));
+}
+
+/// The columns of `json`, to check the exact layout the parser produces,
+/// which `==` does not, since it compares values.
+fn columns(json: &Jsonc) -> (&[Node], &[String], &[f64]) {
+    (&json.nodes, &json.strings, &json.numbers)
+}
+
 #[test]
 fn test() {
     test_parser();
+    test_parse_prefix();
     test_decoder();
+    test_decode_pretty();
+    test_encode_to();
+    test_decode_numbers();
+    test_decode_sorted();
+    test_decode_escaped();
+    test_display();
 }
@@ -1,31 +1,217 @@
+use jsonc::decoder::{decode, decode_slice};
+use jsonc::filter::Filter;
+use jsonc::jq::JqFilter;
+use jsonc::jsonpath::JsonPathQuery;
 use jsonc::parser::parse_value;
+use jsonc::path::JsonPath;
+use jsonc::slice::ValueKind;
+use jsonc::summary::{summarize, SummaryOptions};
 
 fn test_json_get_1() {
     let json_str = std::fs::read_to_string("data/twitter.json").unwrap();
     let parsed_json = parse_value(json_str.as_bytes());
-    let result = parsed_json.get(&["\"search_metadata\"", "\"max_id_str\""]).unwrap();
+    let result = parsed_json
+        .get("search_metadata.max_id_str")
+        .unwrap()
+        .unwrap();
     assert_eq!(&result, "\"505874924095815681\"");
 }
 
 fn test_json_get_2() {
     let json_str = std::fs::read_to_string("data/canada.json").unwrap();
     let parsed_json = parse_value(json_str.as_bytes());
-    let result = parsed_json.get(&["\"type\""]).unwrap();
+    let result = parsed_json.get("type").unwrap().unwrap();
     assert_eq!(&result, "\"FeatureCollection\"");
 }
 
 fn test_json_get_3() {
     let json_str = std::fs::read_to_string("data/citm_catalog.json").unwrap();
     let parsed_json = parse_value(json_str.as_bytes());
-    let result = parsed_json.get(&["\"areaNames\"", "\"205705994\""]).unwrap();
+    let result = parsed_json.get("areaNames.205705994").unwrap().unwrap();
     assert_eq!(&result, "\"1er balcon central\"");
-    let result = parsed_json.get(&["\"topicNames\"", "\"324846100\""]).unwrap();
+    let result = parsed_json.get("topicNames.324846100").unwrap().unwrap();
     assert_eq!(&result, "\"Formations musicales\"");
 }
 
+fn test_json_get_path() {
+    let json_str = std::fs::read_to_string("data/twitter.json").unwrap();
+    let parsed_json = parse_value(json_str.as_bytes());
+    let result = parsed_json.get("statuses[-1].user.screen_name").unwrap();
+    assert_eq!(result.as_deref(), Some("\"2no38mae\""));
+    let path = JsonPath::parse(r#"statuses[-1]["entities"].hashtags[0].indices[1]"#).unwrap();
+    let result = parsed_json.as_slice().get(&path).unwrap().unwrap();
+    assert_eq!(result.as_i64(), Some(64));
+    assert_eq!(parsed_json.get("statuses[100]").unwrap(), None);
+    assert!(parsed_json.get("statuses[x]").is_err());
+}
+
+fn test_extract_many() {
+    let json_str = std::fs::read_to_string("data/twitter.json").unwrap();
+    let parsed_json = parse_value(json_str.as_bytes());
+    let paths: Vec<JsonPath> = [
+        "search_metadata.max_id_str",
+        "statuses[0].user.screen_name",
+        "statuses[-1].user.screen_name",
+        "statuses[99].entities.hashtags[0].text",
+        "statuses[-100].id",
+        "statuses[100]",
+        "search_metadata.missing",
+    ]
+    .iter()
+    .map(|path| JsonPath::parse(path).unwrap())
+    .collect();
+    let found = parsed_json.extract_many(&paths);
+    assert_eq!(found.len(), paths.len());
+    for (path, value) in paths.iter().zip(found) {
+        let expected = parsed_json.as_slice().get_json_path(path);
+        assert_eq!(value.map(decode_slice), expected.map(decode_slice));
+    }
+}
+
+fn test_contains() {
+    let json_str = std::fs::read_to_string("data/twitter.json").unwrap();
+    let parsed_json = parse_value(json_str.as_bytes());
+    let filter = parse_value(br#"{"statuses":[{"user":{"lang":"en"}}]}"#);
+    assert!(parsed_json.contains(&filter.as_slice()));
+    let filter = parse_value(br#"{"search_metadata":{"count":100}}"#);
+    assert!(parsed_json.contains(&filter.as_slice()));
+    let filter = parse_value(br#"{"statuses":[{"user":{"lang":"fr"}}]}"#);
+    assert!(!parsed_json.contains(&filter.as_slice()));
+}
+
+fn test_filter() {
+    let json_str = std::fs::read_to_string("data/twitter.json").unwrap();
+    let parsed_json = parse_value(json_str.as_bytes());
+    let statuses = parsed_json.pointer("/statuses").unwrap();
+    let count = |filter: &str| {
+        let filter = Filter::parse(filter).unwrap();
+        let statuses = statuses.array_iter().unwrap();
+        statuses.filter(|status| filter.matches(*status)).count()
+    };
+    assert_eq!(count(r#"$.retweet_count > 100 && $.user.lang == "ja""#), 2);
+    assert_eq!(count("exists($.entities.urls[0])"), 12);
+    assert_eq!(count("$.user.screen_name like 'a%'"), 7);
+    assert_eq!(count("$.in_reply_to_user_id is null"), 91);
+    assert_eq!(count("$.in_reply_to_user_id > 0 || $.nothing == 1"), 9);
+}
+
+fn test_slice_accessors() {
+    let parsed_json =
+        parse_value(br#"{"s":"x","n":-3,"f":1.5,"b":true,"z":null,"a":[1,[2],{}],"o":{}}"#);
+    let slice = parsed_json.as_slice();
+    assert_eq!(slice.kind(), Some(ValueKind::Object));
+    assert_eq!(slice.len(), Some(7));
+    assert_eq!(slice.get_by_path("s").unwrap().as_str(), Some("x"));
+    assert_eq!(slice.get_by_path("n").unwrap().as_i64(), Some(-3));
+    assert_eq!(slice.get_by_path("f").unwrap().as_i64(), None);
+    assert_eq!(slice.get_by_path("f").unwrap().as_f64(), Some(1.5));
+    assert_eq!(slice.get_by_path("b").unwrap().as_bool(), Some(true));
+    assert!(slice.get_by_path("z").unwrap().is_null());
+    assert_eq!(slice.get_by_path("a").unwrap().len(), Some(3));
+    assert_eq!(slice.get_by_path("a").unwrap().is_empty(), Some(false));
+    assert_eq!(slice.get_by_path("o").unwrap().is_empty(), Some(true));
+    assert_eq!(slice.get_by_path("s").unwrap().len(), None);
+}
+
+fn test_json_pointer() {
+    let json_str = std::fs::read_to_string("data/twitter.json").unwrap();
+    let parsed_json = parse_value(json_str.as_bytes());
+    let result = parsed_json.pointer("/search_metadata/max_id_str").unwrap();
+    assert_eq!(result.as_str(), Some("505874924095815681"));
+    let result = parsed_json.pointer("/statuses/1/user/screen_name").unwrap();
+    assert_eq!(result.as_str(), Some("yuttari1998"));
+    assert!(parsed_json.pointer("/statuses/01").is_none());
+    assert!(parsed_json.pointer("/statuses/-").is_none());
+    assert!(parsed_json.pointer("statuses").is_none());
+
+    let mut parsed_json = parse_value(br#"{"a/b":{"m~n":[1,2]},"":{"":true}}"#);
+    assert_eq!(
+        parsed_json.pointer("/a~1b/m~0n/1").unwrap().as_f64(),
+        Some(2.0)
+    );
+    assert_eq!(parsed_json.pointer("//").unwrap().as_bool(), Some(true));
+    assert!(parsed_json.pointer("/a~2b").is_none());
+    assert_eq!(parsed_json.pointer("").unwrap().len(), Some(2));
+
+    *parsed_json
+        .pointer_mut("/a~1b/m~0n/0")
+        .unwrap()
+        .as_f64_mut()
+        .unwrap() = 5.0;
+    let replacement = parse_value(br#"{"x":["y"]}"#);
+    parsed_json
+        .pointer_mut("//")
+        .unwrap()
+        .replace(replacement.as_slice());
+    assert_eq!(
+        parsed_json,
+        parse_value(br#"{"a/b":{"m~n":[5,2]},"":{"":{"x":["y"]}}}"#)
+    );
+}
+
+fn test_jsonpath_query() {
+    let json_str = std::fs::read_to_string("data/twitter.json").unwrap();
+    let parsed_json = parse_value(json_str.as_bytes());
+    let query = JsonPathQuery::parse("$.statuses[*].user.screen_name").unwrap();
+    let mut names = parsed_json.as_slice().query(&query);
+    let (path, name) = names.next().unwrap();
+    assert_eq!(path.to_string(), "$['statuses'][0]['user']['screen_name']");
+    assert_eq!(name.as_str(), Some("ayuu0123"));
+    assert_eq!(names.count(), 99);
+
+    let query = "$.statuses[?@.user.lang == 'en'].user.screen_name";
+    let query = JsonPathQuery::parse(query).unwrap();
+    assert_eq!(parsed_json.as_slice().query(&query).count(), 2);
+}
+
+fn test_jq_filter() {
+    let json_str = std::fs::read_to_string("data/twitter.json").unwrap();
+    let parsed_json = parse_value(json_str.as_bytes());
+    let filter =
+        JqFilter::parse(r#"[.statuses[] | select(.user.lang == "en") | .user.screen_name]"#)
+            .unwrap();
+    let result = parsed_json.as_slice().jq(&filter).unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].as_slice().len(), Some(2));
+    let filter =
+        JqFilter::parse("{count: .statuses | length, first: .statuses[0].id_str}").unwrap();
+    let result = parsed_json.as_slice().jq(&filter).unwrap();
+    assert_eq!(
+        decode(&result[0]),
+        r#"{"count":100,"first":"505874924095815681"}"#
+    );
+}
+
+fn test_summary() {
+    let json_str = std::fs::read_to_string("data/twitter.json").unwrap();
+    let parsed_json = parse_value(json_str.as_bytes());
+    let options = SummaryOptions {
+        max_depth: 2,
+        max_array_elements: 2,
+        ..SummaryOptions::default()
+    };
+    let summary = summarize(parsed_json.as_slice(), &options);
+    assert!(summary.starts_with(r#"{"statuses":[{…},{…}, … 98 more],"search_metadata":{"#));
+    assert!(summary.contains(r#""next_results":"?max_id="#));
+    assert!(summary.contains("… (+3 chars)"));
+
+    let summary = summarize(parsed_json.as_slice(), &SummaryOptions::default());
+    assert!(summary.len() < 1100);
+    assert!(summary.ends_with(", … 98 more], … 1 more}"));
+}
+
 #[test]
 fn test() {
     test_json_get_1();
     test_json_get_2();
-    test_json_get_3()
+    test_json_get_3();
+    test_json_get_path();
+    test_extract_many();
+    test_contains();
+    test_filter();
+    test_slice_accessors();
+    test_json_pointer();
+    test_jsonpath_query();
+    test_jq_filter();
+    test_summary();
 }